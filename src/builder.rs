@@ -1,17 +1,43 @@
-use std::io::Write;
+use std::hash::{DefaultHasher, Hash, Hasher};
 
-use log::warn;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     factory::Factory,
+    save::{write_atomic, SaveError},
     world::{RecipeId, ResourceId, VariableId, World},
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Hash)]
 pub struct Rule {
     pub variable: VariableId,
     pub constraint: Constraint,
+    /// a soft rule can be broken at a cost of [SOFT_RULE_PENALTY] for each unit it's off by
+    pub soft: bool,
+    /// which soft rules give way first, see [Rule::penalty]
+    pub priority: u32,
+}
+
+impl Rule {
+    /// how much the objective drops for each unit the rule is off by when it's soft
+    ///
+    /// each priority level multiplies [SOFT_RULE_PENALTY] by [SOFT_RULE_PRIORITY_FACTOR],
+    /// so a rule is only broken in favour of a lower priority one when that saves
+    /// more than [SOFT_RULE_PRIORITY_FACTOR] times as much violation
+    pub fn penalty(&self) -> f64 {
+        SOFT_RULE_PENALTY
+            * SOFT_RULE_PRIORITY_FACTOR.powi(self.priority.min(MAX_SOFT_RULE_PRIORITY) as i32)
+    }
+
+    /// how far a value of the rule's variable is from satisfying the constraint
+    pub fn violation(&self, value: f64) -> f64 {
+        match self.constraint {
+            Constraint::Less(rate) => (value - rate).max(0.),
+            Constraint::Equal(rate) => (value - rate).abs(),
+            Constraint::Greater(rate) => (rate - value).max(0.),
+            Constraint::Unconstrained => 0.,
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -22,9 +48,59 @@ pub enum Constraint {
     Unconstrained,
 }
 
-#[derive(Default)]
+/// hashes rates by their bit pattern so identical constraints produce identical hashes
+impl Hash for Constraint {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            Constraint::Less(rate) | Constraint::Equal(rate) | Constraint::Greater(rate) => {
+                rate.to_bits().hash(state)
+            }
+            Constraint::Unconstrained => (),
+        }
+    }
+}
+
+#[derive(Default, Clone)]
 pub struct RuleList {
     pub rules: Vec<Rule>,
+    /// weighted variables to maximize, saved alongside the rules
+    pub optimizations: Vec<(VariableId, f64)>,
+    /// resources made together in set proportions, each adding an `Equal` rule per resource
+    pub baskets: Vec<Basket>,
+}
+
+/// resources made together in set proportions, like the parts something is assembled from
+#[derive(Clone)]
+pub struct Basket {
+    /// each resource with its amount relative to the others
+    pub resources: Vec<(ResourceId, f64)>,
+    /// the per minute rate of one relative amount
+    pub scale: f64,
+}
+
+impl Basket {
+    /// an `Equal` rule on each resource at its relative amount times the scale
+    pub fn rules(&self) -> impl Iterator<Item = Rule> + '_ {
+        self.resources.iter().map(|&(resource, amount)| Rule {
+            variable: resource.variable_id(),
+            constraint: Constraint::Equal(self.scale * amount),
+            soft: false,
+            priority: 0,
+        })
+    }
+}
+
+impl RuleList {
+    /// the rules together with the rules of the baskets
+    pub fn all_rules(&self) -> Vec<Rule> {
+        self.rules
+            .iter()
+            .copied()
+            .chain(self.baskets.iter().flat_map(Basket::rules))
+            .collect()
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -32,16 +108,75 @@ pub enum RuleJson {
     Resource {
         resource: String,
         constraint: Constraint,
+        #[serde(default)]
+        soft: bool,
+        #[serde(default)]
+        priority: u32,
     },
     Recipe {
         recipe: String,
         constraint: Constraint,
+        #[serde(default)]
+        soft: bool,
+        #[serde(default)]
+        priority: u32,
     },
 }
 
+/// a resource or recipe referred to by name
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum VariableRef {
+    Resource(String),
+    Recipe(String),
+}
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct RuleListJson {
     pub rules: Vec<RuleJson>,
+    #[serde(default)]
+    pub optimizations: Vec<(VariableRef, f64)>,
+    #[serde(default)]
+    pub baskets: Vec<BasketJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BasketJson {
+    /// resource names with their relative amounts
+    pub resources: Vec<(String, f64)>,
+    pub scale: f64,
+}
+
+impl VariableRef {
+    pub fn new(world: &World, variable: VariableId) -> Self {
+        match variable {
+            VariableId::Resource(resource) => {
+                VariableRef::Resource(world.name_of_resource(resource).into())
+            }
+            VariableId::Recipe(recipe) => VariableRef::Recipe(world.name_of_recipe(recipe).into()),
+        }
+    }
+
+    /// a reference of the same kind with a different name
+    pub fn renamed(&self, name: &str) -> Self {
+        match self {
+            VariableRef::Resource(_) => VariableRef::Resource(name.into()),
+            VariableRef::Recipe(_) => VariableRef::Recipe(name.into()),
+        }
+    }
+
+    pub fn resolve(self, world: &World) -> Result<VariableId, LoadRuleListError> {
+        match self {
+            VariableRef::Resource(resource_name) => match world.resource_id_of_name(&resource_name)
+            {
+                Some(resource) => Ok(resource.variable_id()),
+                None => Err(LoadRuleListError::BadResourceName { resource_name }),
+            },
+            VariableRef::Recipe(recipe_name) => match world.recipe_id_of_name(&recipe_name) {
+                Some(recipe) => Ok(recipe.variable_id()),
+                None => Err(LoadRuleListError::BadRecipeName { recipe_name }),
+            },
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -67,203 +202,2447 @@ pub fn load_rule_list(
         Err(err) => return Err(LoadRuleListError::JsonError(err)),
     };
 
-    let mut rule_list = RuleList::default();
-
-    for rule in rule_list_json.rules {
-        match rule {
-            RuleJson::Resource {
-                resource,
-                constraint,
-            } => {
-                let Some(resource) = world.resource_id_of_name(&resource) else {
-                    return Err(LoadRuleListError::BadResourceName {
-                        resource_name: resource,
-                    });
-                };
+    rule_list_json.into_rule_list(world)
+}
 
-                rule_list.rules.push(Rule {
-                    variable: resource.variable_id(),
-                    constraint,
-                });
+pub fn save_rule_list(
+    world: &World,
+    rule_list: &RuleList,
+    path: impl AsRef<std::path::Path>,
+) -> Result<(), SaveError> {
+    let rule_list_json = RuleListJson::new(world, rule_list);
+
+    write_atomic(
+        path,
+        serde_json::to_string_pretty(&rule_list_json)
+            .expect("Failed to convert to json")
+            .as_bytes(),
+    )
+}
+
+/// rewrites a saved rule list so every reference to `old` refers to `new_name` instead,
+/// for migrating rule lists after a world renames something
+pub fn remap_rule_list(
+    path: impl AsRef<std::path::Path>,
+    old: &VariableRef,
+    new_name: &str,
+) -> Result<(), LoadRuleListError> {
+    let file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(err) => return Err(LoadRuleListError::IoError(err)),
+    };
+
+    let mut rule_list_json: RuleListJson =
+        match serde_json::from_reader(std::io::BufReader::new(file)) {
+            Ok(rule_list) => rule_list,
+            Err(err) => return Err(LoadRuleListError::JsonError(err)),
+        };
+
+    rule_list_json.remap(old, new_name);
+
+    write_atomic(
+        path,
+        serde_json::to_string_pretty(&rule_list_json)
+            .expect("Failed to convert to json")
+            .as_bytes(),
+    )
+    .map_err(|SaveError::IoError(err)| LoadRuleListError::IoError(err))
+}
+
+impl RuleListJson {
+    pub fn new(world: &World, rule_list: &RuleList) -> Self {
+        let mut rule_list_json = RuleListJson::default();
+
+        for rule in rule_list.rules.iter() {
+            rule_list_json.rules.push(match rule.variable {
+                VariableId::Resource(resource) => RuleJson::Resource {
+                    resource: world.name_of_resource(resource).into(),
+                    constraint: rule.constraint,
+                    soft: rule.soft,
+                    priority: rule.priority,
+                },
+                VariableId::Recipe(recipe) => RuleJson::Recipe {
+                    recipe: world.name_of_recipe(recipe).into(),
+                    constraint: rule.constraint,
+                    soft: rule.soft,
+                    priority: rule.priority,
+                },
+            });
+        }
+
+        for &(variable, coefficient) in rule_list.optimizations.iter() {
+            rule_list_json
+                .optimizations
+                .push((VariableRef::new(world, variable), coefficient));
+        }
+
+        for basket in rule_list.baskets.iter() {
+            rule_list_json.baskets.push(BasketJson {
+                resources: basket
+                    .resources
+                    .iter()
+                    .map(|&(resource, amount)| (world.name_of_resource(resource).into(), amount))
+                    .collect(),
+                scale: basket.scale,
+            });
+        }
+
+        rule_list_json
+    }
+
+    /// points every reference to `old` at `new_name` instead
+    pub fn remap(&mut self, old: &VariableRef, new_name: &str) {
+        for rule in self.rules.iter_mut() {
+            match (rule, old) {
+                (RuleJson::Resource { resource, .. }, VariableRef::Resource(old_name))
+                    if resource == old_name =>
+                {
+                    *resource = new_name.into()
+                }
+                (RuleJson::Recipe { recipe, .. }, VariableRef::Recipe(old_name))
+                    if recipe == old_name =>
+                {
+                    *recipe = new_name.into()
+                }
+                _ => (),
             }
-            RuleJson::Recipe { recipe, constraint } => {
-                let Some(recipe) = world.recipe_id_of_name(&recipe) else {
-                    return Err(LoadRuleListError::BadRecipeName {
-                        recipe_name: recipe,
-                    });
-                };
+        }
 
-                rule_list.rules.push(Rule {
-                    variable: recipe.variable_id(),
-                    constraint,
-                });
+        for (variable, _) in self.optimizations.iter_mut() {
+            if variable == old {
+                *variable = old.renamed(new_name);
+            }
+        }
+
+        if let VariableRef::Resource(old_name) = old {
+            for basket in self.baskets.iter_mut() {
+                for (resource, _) in basket.resources.iter_mut() {
+                    if resource == old_name {
+                        *resource = new_name.into();
+                    }
+                }
             }
         }
     }
 
-    Ok(rule_list)
-}
+    /// converts names back into ids within a world
+    pub fn into_rule_list(self, world: &World) -> Result<RuleList, LoadRuleListError> {
+        let mut rule_list = RuleList::default();
 
-pub fn save_rule_list(world: &World, rule_list: &RuleList, path: impl AsRef<std::path::Path>) {
-    let mut rule_list_json = RuleListJson::default();
+        for rule in self.rules {
+            match rule {
+                RuleJson::Resource {
+                    resource,
+                    constraint,
+                    soft,
+                    priority,
+                } => {
+                    let Some(resource) = world.resource_id_of_name(&resource) else {
+                        return Err(LoadRuleListError::BadResourceName {
+                            resource_name: resource,
+                        });
+                    };
 
-    for rule in rule_list.rules.iter() {
-        rule_list_json.rules.push(match rule.variable {
-            VariableId::Resource(resource) => RuleJson::Resource {
-                resource: world.name_of_resource(resource).into(),
-                constraint: rule.constraint,
-            },
-            VariableId::Recipe(recipe) => RuleJson::Recipe {
-                recipe: world.name_of_recipe(recipe).into(),
-                constraint: rule.constraint,
-            },
-        });
-    }
+                    rule_list.rules.push(Rule {
+                        variable: resource.variable_id(),
+                        constraint,
+                        soft,
+                        priority,
+                    });
+                }
+                RuleJson::Recipe {
+                    recipe,
+                    constraint,
+                    soft,
+                    priority,
+                } => {
+                    let Some(recipe) = world.recipe_id_of_name(&recipe) else {
+                        return Err(LoadRuleListError::BadRecipeName {
+                            recipe_name: recipe,
+                        });
+                    };
 
-    let mut file = match std::fs::File::create(path) {
-        Ok(file) => file,
-        Err(err) => {
-            warn!("failed to open rule list file: {:?}", err);
-            return;
+                    rule_list.rules.push(Rule {
+                        variable: recipe.variable_id(),
+                        constraint,
+                        soft,
+                        priority,
+                    });
+                }
+            }
         }
-    };
 
-    if let Err(err) = file.write_all(
-        serde_json::to_string(&rule_list_json)
-            .expect("Failed to convert to json")
-            .as_bytes(),
-    ) {
-        warn!("failed to write to rule list file: {:?}", err);
+        for (variable, coefficient) in self.optimizations {
+            rule_list
+                .optimizations
+                .push((variable.resolve(world)?, coefficient));
+        }
+
+        for basket in self.baskets {
+            let mut resources = Vec::new();
+
+            for (resource_name, amount) in basket.resources {
+                let Some(resource) = world.resource_id_of_name(&resource_name) else {
+                    return Err(LoadRuleListError::BadResourceName { resource_name });
+                };
+
+                resources.push((resource, amount));
+            }
+
+            rule_list.baskets.push(Basket {
+                resources,
+                scale: basket.scale,
+            });
+        }
+
+        Ok(rule_list)
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Problem {
     pub rules: Vec<Rule>,
+    /// weighted variables to maximize, entries for the same variable are summed
     pub optimizations: Vec<(VariableId, f64)>,
+    /// when there are no optimizations, accept any feasible solution
+    /// instead of minimizing the intake of raw resources
+    pub any_feasible: bool,
+    /// each element `(a, b, factor)` keeps `a` at exactly `factor` times `b`
+    pub ratios: Vec<(VariableId, VariableId, f64)>,
+    /// each element `(a, b, fraction)` keeps `a` at most `fraction` times `b`
+    pub fractions: Vec<(VariableId, VariableId, f64)>,
+    /// recipes that may not be used
+    pub disabled: Vec<RecipeId>,
+    /// recipes that must run, at least [REQUIRED_RECIPE_MINIMUM] machines each
+    pub required: Vec<RecipeId>,
+    /// resources supplied from outside the factory, each with the most that can be brought in
+    ///
+    /// an import relaxes the resource's default net of zero to anywhere between `-cap` and zero.
+    /// raw resources can already be consumed freely, and a rule on the resource takes precedence.
+    /// unlike a rule, an import leaves the resource out of the default raw resource minimization,
+    /// so it's used as freely as the cap allows
+    pub imports: Vec<(ResourceId, f64)>,
+    /// keep recipes below the zero threshold that aren't exactly zero, for debugging
+    pub keep_zero: bool,
+    /// recipes running at fewer machines than this are treated as unused,
+    /// defaults to half the smallest step [SOLUTION_ROUND_PRECISION] rounds to
+    pub zero_threshold: Option<f64>,
+    /// let resources made inside the factory have a surplus instead of an exact balance,
+    /// and reject solutions where rounding leaves any resource consumed faster than it's supplied
+    pub strict_balance: bool,
+    /// each element keeps a weighted sum of variables at or above a value
+    pub minimums: Vec<(Vec<(VariableId, f64)>, f64)>,
+    /// each element constrains the total machines of all the recipes with a tag
+    pub tag_rules: Vec<(String, Constraint)>,
+    /// maximize the sink points of surplus resources, letting any resource with a point value be sunk
+    pub sink_points: bool,
+    /// variables whose smallest value is maximized alongside the optimizations, see [Problem::maximize_min]
+    pub balanced: Vec<VariableId>,
 }
 
 pub const SOLUTION_ROUND_PRECISION: f64 = 1_000_000.;
 
-impl Problem {
-    pub fn solve(&self, world: &World) -> Result<Factory, String> {
-        let mut problem = minilp::Problem::new(minilp::OptimizationDirection::Maximize);
+/// rates below this round to zero
+pub const DEFAULT_ZERO_THRESHOLD: f64 = 0.5 / SOLUTION_ROUND_PRECISION;
 
-        // add all the problem variables
+/// how much each later recipe is penalized in the objective, so that ties between equally good
+/// solutions are always broken the same way, towards recipes listed earlier in the world
+const TIE_BREAK_WEIGHT: f64 = 1e-9;
 
-        let mut resource_coefficients = vec![0.; world.resources.len()];
-        let mut recipe_coefficients = vec![0.; world.recipes.len()];
+/// how far below its allowed range a resource may end up with [Problem::strict_balance]
+const STRICT_BALANCE_TOLERANCE: f64 = 1e-9;
+
+/// how far, relative to its size, an earlier objective may fall while optimizing later ones
+const LEXICOGRAPHIC_SLACK: f64 = 1e-9;
+
+/// a solved factory with the value achieved for the optimizations, or why it couldn't be solved
+pub type SolveResult = Result<(Factory, f64), String>;
+
+/// how much the objective drops for each unit a soft rule is off by, large enough to outweigh
+/// everything else so soft rules are only broken when they have to be
+pub const SOFT_RULE_PENALTY: f64 = 1000.;
+
+/// how many times more each soft rule priority level costs to break than the one below
+pub const SOFT_RULE_PRIORITY_FACTOR: f64 = 10.;
+
+/// the highest priority that raises the penalty, higher ones are treated as this
+/// since larger penalties make the solver numerically unstable
+pub const MAX_SOFT_RULE_PRIORITY: u32 = 5;
+
+/// the fewest machines a required recipe runs, small so it barely changes the rest of the solution
+/// but large enough to survive rounding
+pub const REQUIRED_RECIPE_MINIMUM: f64 = 0.01;
+
+/// how much a recipe preference of 1 adds to that recipe's objective coefficient
+///
+/// kept small so preferences only decide between otherwise similar solutions
+pub const PREFERENCE_WEIGHT: f64 = 0.001;
+
+/// the recipes a factory runs at fewer than `threshold` machines
+fn marginal_recipes(factory: &Factory, threshold: f64) -> Vec<RecipeId> {
+    factory
+        .recipes
+        .iter()
+        .filter(|&&(_, rate)| rate < threshold)
+        .map(|&(recipe, _)| recipe)
+        .collect()
+}
+
+/// solves each rule list on its own in place of the rules of `base`, keeping its optimizations
+/// and every other setting, for comparing alternative designs
+pub fn solve_all(
+    world: &World,
+    base: &Problem,
+    lists: &[RuleList],
+) -> Vec<Result<Factory, String>> {
+    lists
+        .iter()
+        .map(|rule_list| {
+            let problem = Problem {
+                rules: rule_list.all_rules(),
+                ..base.clone()
+            };
+
+            problem.solve(world).map(|(factory, _)| factory)
+        })
+        .collect()
+}
+
+/// the raw resources consumed, power used and machines of a solution, or why it couldn't be solved
+pub type ObjectiveMetrics = Result<(f64, f64, f64), String>;
 
+/// solves a problem once for each named objective in place of its own optimizations,
+/// collecting the metrics of each solution to compare the trade-offs between them
+pub fn compare_objectives(
+    world: &World,
+    problem: &Problem,
+    objectives: Vec<(String, Vec<(VariableId, f64)>)>,
+) -> Vec<(String, ObjectiveMetrics)> {
+    objectives
+        .into_iter()
+        .map(|(name, optimizations)| {
+            // an empty objective falls back to minimizing raw resources
+            let problem = Problem {
+                optimizations,
+                any_feasible: false,
+                sink_points: false,
+                balanced: Vec::new(),
+                ..problem.clone()
+            };
+
+            let metrics = problem.solve(world).map(|(factory, _)| {
+                (
+                    factory.raw_intake(world),
+                    factory.power_usage(world),
+                    factory.total_machines(false),
+                )
+            });
+
+            (name, metrics)
+        })
+        .collect()
+}
+
+/// two problems that would produce the same solution hash the same
+impl Hash for Problem {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rules.hash(state);
+
+        self.optimizations.len().hash(state);
         for &(variable, coefficient) in self.optimizations.iter() {
-            match variable {
-                VariableId::Resource(ResourceId(index)) => {
-                    resource_coefficients[index] = coefficient
-                }
-                VariableId::Recipe(RecipeId(index)) => recipe_coefficients[index] = coefficient,
+            variable.hash(state);
+            coefficient.to_bits().hash(state);
+        }
+
+        self.any_feasible.hash(state);
+
+        self.ratios.len().hash(state);
+        for &(a, b, factor) in self.ratios.iter() {
+            a.hash(state);
+            b.hash(state);
+            factor.to_bits().hash(state);
+        }
+
+        self.fractions.len().hash(state);
+        for &(a, b, fraction) in self.fractions.iter() {
+            a.hash(state);
+            b.hash(state);
+            fraction.to_bits().hash(state);
+        }
+
+        self.disabled.hash(state);
+        self.required.hash(state);
+
+        self.imports.len().hash(state);
+        for &(resource, cap) in self.imports.iter() {
+            resource.hash(state);
+            cap.to_bits().hash(state);
+        }
+
+        self.keep_zero.hash(state);
+        self.zero_threshold.map(f64::to_bits).hash(state);
+        self.strict_balance.hash(state);
+
+        self.minimums.len().hash(state);
+        for (terms, value) in self.minimums.iter() {
+            terms.len().hash(state);
+            for &(variable, weight) in terms.iter() {
+                variable.hash(state);
+                weight.to_bits().hash(state);
             }
+            value.to_bits().hash(state);
         }
 
-        let resource_variables: Vec<_> = resource_coefficients
-            .into_iter()
-            .map(|coefficient| problem.add_var(coefficient, (f64::NEG_INFINITY, f64::INFINITY)))
-            .collect();
+        self.tag_rules.hash(state);
+        self.sink_points.hash(state);
+        self.balanced.hash(state);
+    }
+}
 
-        let recipe_variables: Vec<_> = recipe_coefficients
-            .into_iter()
-            .map(|coefficient| problem.add_var(coefficient, (f64::NEG_INFINITY, f64::INFINITY)))
-            .collect();
+impl Problem {
+    /// maximizes the smallest of `outputs` rather than their sum, so they end up as even as possible
+    ///
+    /// this adds a variable `t` to the objective with a constraint `output >= t` for each output.
+    /// resource outputs may have a surplus without rules of their own, though without limits on
+    /// the inputs the problem is unbounded
+    pub fn maximize_min(&mut self, outputs: Vec<VariableId>) {
+        self.balanced = outputs;
+    }
 
-        // add all the recipe constraints
-        //
-        // the net usage of a resource should be the sum
-        // of all the recipes that produce or consume it
-        // times the rate for each recipe
+    /// a problem producing exactly `rate` of a resource while using as little raw resources as possible
+    ///
+    /// raw resources are allowed to be consumed but not produced
+    pub fn for_output(world: &World, resource: ResourceId, rate: f64) -> Self {
+        let mut problem = Problem::default();
 
-        let mut resource_recipe_coefficients = vec![Vec::new(); world.resources.len()];
+        problem.rules.push(Rule {
+            variable: resource.variable_id(),
+            constraint: Constraint::Equal(rate),
+            soft: false,
+            priority: 0,
+        });
 
-        for (recipe_index, recipe) in world.recipes.iter().enumerate() {
-            let recipe_variable = recipe_variables[recipe_index];
+        for raw_resource in world.raw_resources() {
+            if raw_resource == resource {
+                continue;
+            }
 
-            for &(ResourceId(resource_index), rate) in recipe.rates.iter() {
-                resource_recipe_coefficients[resource_index].push((recipe_variable, rate));
+            problem.rules.push(Rule {
+                variable: raw_resource.variable_id(),
+                constraint: Constraint::Less(0.),
+                soft: false,
+                priority: 0,
+            });
+
+            // maximizing the net of a consumed resource minimizes its consumption
+            problem.optimizations.push((raw_resource.variable_id(), 1.));
+        }
+
+        problem
+    }
+
+    /// a key identifying this problem within a world, used to cache solutions
+    ///
+    /// the world's resource names and recipes are included so that solutions from a different world,
+    /// or the same world after its file was edited, are not reused
+    pub fn cache_key(&self, world: &World) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for resource in world.resources.iter() {
+            resource.name.hash(&mut hasher);
+        }
+
+        world.recipes.len().hash(&mut hasher);
+        for recipe in world.recipes.iter() {
+            recipe.name.hash(&mut hasher);
+            recipe.rates.len().hash(&mut hasher);
+            for &(resource, rate) in recipe.rates.iter() {
+                resource.hash(&mut hasher);
+                rate.to_bits().hash(&mut hasher);
             }
         }
 
-        for (resource_index, mut recipe_coefficients) in
-            resource_recipe_coefficients.into_iter().enumerate()
-        {
-            recipe_coefficients.push((resource_variables[resource_index], -1.));
+        self.hash(&mut hasher);
 
-            problem.add_constraint(recipe_coefficients, minilp::ComparisonOp::Eq, 0.)
+        hasher.finish()
+    }
+
+    /// optimizes each objective in turn, keeping every earlier objective at the best value it reached
+    ///
+    /// returns the final factory and the value achieved for each objective in order
+    pub fn solve_lexicographic(
+        &self,
+        world: &World,
+        objectives: &[Vec<(VariableId, f64)>],
+    ) -> Result<(Factory, Vec<f64>), String> {
+        let mut problem = self.clone();
+        let mut values = Vec::new();
+        let mut factory = Factory::default();
+
+        for objective in objectives.iter().filter(|objective| !objective.is_empty()) {
+            problem.optimizations = objective.clone();
+
+            let (solved, _) = problem.solve(world)?;
+
+            // the solver's objective can include sink points or a balanced minimum,
+            // so the floor is the objective's own weighted sum in the solved factory
+            let net_resources = solved.net_resources(world);
+            let value = objective
+                .iter()
+                .map(|&(variable, weight)| weight * solved.value_of(&net_resources, variable))
+                .sum::<f64>();
+
+            // leave a little slack so rounding in the solver can't make the next stage infeasible
+            let slack = LEXICOGRAPHIC_SLACK * value.abs().max(1.);
+            problem.minimums.push((objective.clone(), value - slack));
+
+            values.push(value);
+            factory = solved;
         }
 
-        // limit all recipes to be positive
+        Ok((factory, values))
+    }
 
-        for &recipe_variable in recipe_variables.iter() {
-            problem.add_constraint(&[(recipe_variable, 1.)], minilp::ComparisonOp::Ge, 0.);
+    /// solves the problem, then solves again with any recipe using fewer than `threshold` machines disabled
+    ///
+    /// a true penalty on the number of distinct recipes would need integer programming,
+    /// this approximates it by pruning recipes that only make a marginal contribution.
+    /// if the pruned problem can't be solved the unpruned solution is returned
+    pub fn prune_marginal(&self, world: &World, threshold: f64) -> SolveResult {
+        let (factory, objective) = self.solve(world)?;
+
+        let marginal = marginal_recipes(&factory, threshold);
+
+        if marginal.is_empty() {
+            return Ok((factory, objective));
         }
 
-        // add user constraints
+        let mut pruned = self.clone();
+        pruned.disabled.extend(marginal);
 
-        // whether to constrain a resource net value to 0 by default
-        let mut resource_default = vec![true; world.resources.len()];
+        Ok(pruned.solve(world).unwrap_or((factory, objective)))
+    }
 
-        for rule in self.rules.iter() {
-            // if there is any rule specified for a resource, don't apply the default rule
-            if let VariableId::Resource(ResourceId(index)) = rule.variable {
-                resource_default[index] = false;
-            }
+    /// [Problem::prune_marginal] with the objectives optimized in turn like [Problem::solve_lexicographic]
+    pub fn prune_marginal_lexicographic(
+        &self,
+        world: &World,
+        objectives: &[Vec<(VariableId, f64)>],
+        threshold: f64,
+    ) -> Result<(Factory, Vec<f64>), String> {
+        let (factory, values) = self.solve_lexicographic(world, objectives)?;
 
-            let (operator, rhs) = match rule.constraint {
-                Constraint::Less(rate) => (minilp::ComparisonOp::Le, rate),
-                Constraint::Equal(rate) => (minilp::ComparisonOp::Eq, rate),
-                Constraint::Greater(rate) => (minilp::ComparisonOp::Ge, rate),
-                Constraint::Unconstrained => continue,
-            };
+        let marginal = marginal_recipes(&factory, threshold);
 
-            let variable = match rule.variable {
-                VariableId::Resource(ResourceId(index)) => resource_variables[index],
-                VariableId::Recipe(RecipeId(index)) => recipe_variables[index],
-            };
+        if marginal.is_empty() {
+            return Ok((factory, values));
+        }
+
+        let mut pruned = self.clone();
+        pruned.disabled.extend(marginal);
+
+        Ok(pruned
+            .solve_lexicographic(world, objectives)
+            .unwrap_or((factory, values)))
+    }
 
-            problem.add_constraint(&[(variable, 1.)], operator, rhs);
+    /// how much the objective drops from `factory` when a recipe can't be used, none if the problem is infeasible without it
+    ///
+    /// `solve` is called with the problem without the recipe, and should solve it the same way `factory` was solved
+    /// so the loss is measured against that factory. this compares everything the solver maximizes,
+    /// so without optimizations it's the extra raw resources used
+    pub fn loss_without(
+        &self,
+        world: &World,
+        factory: &Factory,
+        recipe: RecipeId,
+        solve: impl FnOnce(&Problem) -> SolveResult,
+    ) -> Result<Option<f64>, String> {
+        let mut without = self.clone();
+        without.disabled.push(recipe);
+
+        match solve(&without) {
+            Ok((without_factory, _)) => Ok(Some(
+                self.full_objective(world, factory) - self.full_objective(world, &without_factory),
+            )),
+            // infeasibility is explained in many ways, so check the problem itself
+            Err(response) => match without.solve_unexplained(world) {
+                Err(unexplained) if unexplained == "Infeasible" => Ok(None),
+                _ => Err(response),
+            },
         }
+    }
 
-        // add default resource constraints
+    /// the value of everything the solver maximizes for a factory, including the default
+    /// raw resource minimization and preference nudges
+    fn full_objective(&self, world: &World, factory: &Factory) -> f64 {
+        let (resource_coefficients, recipe_coefficients) = self.objective_coefficients(world);
+        let net_resources = factory.net_resources(world);
+
+        let resources: f64 = resource_coefficients
+            .iter()
+            .zip(net_resources.resources.iter())
+            .map(|(coefficient, (rate, _))| coefficient * rate)
+            .sum();
+
+        let recipes: f64 = factory
+            .recipes
+            .iter()
+            .map(|&(recipe, rate)| recipe_coefficients[recipe.0] * rate)
+            .sum();
+
+        resources + recipes
+    }
 
-        for (index, constrain) in resource_default.into_iter().enumerate() {
-            if constrain {
-                let resource_variable = resource_variables[index];
-                problem.add_constraint(&[(resource_variable, 1.)], minilp::ComparisonOp::Eq, 0.);
+    /// checks that the rules on each variable can all hold at once, like two `Equal` rules with different rates can't,
+    /// naming them instead of leaving the solver to report an infeasible problem
+    ///
+    /// soft rules are left out since they can give way to each other
+    fn check_conflicting_rules(&self, world: &World) -> Result<(), String> {
+        let hard_rules = || self.rules.iter().filter(|rule| !rule.soft);
+
+        let mut variables = Vec::new();
+
+        for rule in hard_rules() {
+            if !variables.contains(&rule.variable) {
+                variables.push(rule.variable);
             }
         }
 
-        // solve
+        for variable in variables {
+            let constraints: Vec<_> = hard_rules()
+                .filter(|rule| rule.variable == variable)
+                .map(|rule| rule.constraint)
+                .collect();
 
-        let solution = match problem.solve() {
-            Ok(solution) => solution,
-            Err(error) => {
-                return Err(match error {
-                    minilp::Error::Infeasible => "Infeasible".into(),
-                    minilp::Error::Unbounded => "Unbounded".into(),
-                });
+            let mut min = f64::NEG_INFINITY;
+            let mut max = f64::INFINITY;
+
+            for &constraint in constraints.iter() {
+                match constraint {
+                    Constraint::Less(rate) => max = max.min(rate),
+                    Constraint::Equal(rate) => {
+                        min = min.max(rate);
+                        max = max.min(rate);
+                    }
+                    Constraint::Greater(rate) => min = min.max(rate),
+                    Constraint::Unconstrained => {}
+                }
             }
-        };
 
-        let mut factory = Factory::default();
+            if min > max {
+                let rules: Vec<_> = constraints
+                    .iter()
+                    .filter_map(|&constraint| match constraint {
+                        Constraint::Less(rate) => Some(format!("<= {}", rate)),
+                        Constraint::Equal(rate) => Some(format!("= {}", rate)),
+                        Constraint::Greater(rate) => Some(format!(">= {}", rate)),
+                        Constraint::Unconstrained => None,
+                    })
+                    .collect();
+
+                return Err(format!(
+                    "The rules on {} conflict: {}",
+                    world.name_of_variable(variable),
+                    rules.join(", ")
+                ));
+            }
+        }
 
-        for (index, recipe_variable) in recipe_variables.into_iter().enumerate() {
-            let rate = *solution.var_value(recipe_variable);
+        Ok(())
+    }
 
-            let rate = (rate * SOLUTION_ROUND_PRECISION).round() / SOLUTION_ROUND_PRECISION;
+    /// checks that every number in the problem is finite, naming the first that isn't
+    fn check_finite(&self, world: &World) -> Result<(), String> {
+        for rule in self.rules.iter() {
+            if let Constraint::Less(rate) | Constraint::Equal(rate) | Constraint::Greater(rate) =
+                rule.constraint
+            {
+                if !rate.is_finite() {
+                    return Err(format!(
+                        "The rule on {} has a non-finite rate",
+                        world.name_of_variable(rule.variable)
+                    ));
+                }
+            }
+        }
 
-            if rate.abs() < f64::EPSILON {
-                continue;
+        for &(variable, coefficient) in self.optimizations.iter() {
+            if !coefficient.is_finite() {
+                return Err(format!(
+                    "The optimization of {} has a non-finite weight",
+                    world.name_of_variable(variable)
+                ));
             }
+        }
 
-            factory.recipes.push((RecipeId(index), rate));
+        for &(a, b, factor) in self.ratios.iter() {
+            if !factor.is_finite() {
+                return Err(format!(
+                    "The ratio between {} and {} has a non-finite factor",
+                    world.name_of_variable(a),
+                    world.name_of_variable(b)
+                ));
+            }
+        }
+
+        for &(a, b, fraction) in self.fractions.iter() {
+            if !fraction.is_finite() {
+                return Err(format!(
+                    "The fraction of {} in {} is non-finite",
+                    world.name_of_variable(a),
+                    world.name_of_variable(b)
+                ));
+            }
         }
 
-        Ok(factory)
+        for (terms, value) in self.minimums.iter() {
+            if !value.is_finite() || terms.iter().any(|(_, weight)| !weight.is_finite()) {
+                return Err("A minimum has a non-finite value".into());
+            }
+        }
+
+        for (tag, constraint) in self.tag_rules.iter() {
+            if let Constraint::Less(rate) | Constraint::Equal(rate) | Constraint::Greater(rate) =
+                constraint
+            {
+                if !rate.is_finite() {
+                    return Err(format!("The rule on tag {} has a non-finite rate", tag));
+                }
+            }
+        }
+
+        for &(resource, cap) in self.imports.iter() {
+            if !cap.is_finite() {
+                return Err(format!(
+                    "The import of {} has a non-finite cap",
+                    world.name_of_resource(resource)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// the objective coefficients of each resource and each recipe
+    fn objective_coefficients(&self, world: &World) -> (Vec<f64>, Vec<f64>) {
+        let mut resource_coefficients = vec![0.; world.resources.len()];
+        let mut recipe_coefficients = vec![0.; world.recipes.len()];
+
+        // optimizations targeting the same variable accumulate
+        for &(variable, coefficient) in self.optimizations.iter() {
+            match variable {
+                VariableId::Resource(ResourceId(index)) => {
+                    resource_coefficients[index] += coefficient
+                }
+                VariableId::Recipe(RecipeId(index)) => recipe_coefficients[index] += coefficient,
+            }
+        }
+
+        if self.sink_points {
+            for (resource, coefficient) in
+                world.resources.iter().zip(resource_coefficients.iter_mut())
+            {
+                *coefficient += resource.points.unwrap_or(0.);
+            }
+        }
+
+        // without an objective, minilp returns an arbitrary feasible point which can be wasteful,
+        // so default to maximizing the net of raw resources, which minimizes their consumption
+        if self.optimizations.is_empty()
+            && !self.sink_points
+            && self.balanced.is_empty()
+            && !self.any_feasible
+        {
+            for resource in world.raw_resources() {
+                if !self.imports.iter().any(|&(import, _)| import == resource) {
+                    resource_coefficients[resource.0] += 1.;
+                }
+            }
+        }
+
+        // nudge the solver towards preferred recipes
+        for (recipe, coefficient) in world.recipes.iter().zip(recipe_coefficients.iter_mut()) {
+            *coefficient += recipe.preference * PREFERENCE_WEIGHT;
+        }
+
+        // degenerate problems have many optimal vertices, breaking ties by recipe order makes
+        // the solver settle on the same one every time
+        for (index, coefficient) in recipe_coefficients.iter_mut().enumerate() {
+            *coefficient -= (index + 1) as f64 * TIE_BREAK_WEIGHT;
+        }
+
+        (resource_coefficients, recipe_coefficients)
+    }
+
+    /// the range each resource's net is kept within when no rule mentions it
+    ///
+    /// resources made inside the factory default to a net of zero, raw resources that no recipe
+    /// produces can be consumed without limit but not created, and imports allow a limited net consumption.
+    /// when maximizing sink points, resources with a point value may also have a surplus,
+    /// as may every resource made inside the factory with a strict balance
+    fn default_resource_ranges(&self, world: &World) -> Vec<Option<(f64, f64)>> {
+        let max = if self.strict_balance {
+            f64::INFINITY
+        } else {
+            0.
+        };
+
+        let mut ranges: Vec<Option<(f64, f64)>> = vec![Some((0., max)); world.resources.len()];
+
+        for ResourceId(index) in world.raw_resources() {
+            ranges[index] = Some((f64::NEG_INFINITY, 0.));
+        }
+
+        for &(ResourceId(index), cap) in self.imports.iter() {
+            if let Some((min, _)) = &mut ranges[index] {
+                *min = f64::min(*min, -cap);
+            }
+        }
+
+        // a surplus of a resource worth points can be sunk
+        if self.sink_points {
+            for (index, resource) in world.resources.iter().enumerate() {
+                if resource.points.is_some() {
+                    if let Some((_, max)) = &mut ranges[index] {
+                        *max = f64::INFINITY;
+                    }
+                }
+            }
+        }
+
+        // balanced outputs are made as a surplus
+        for &variable in self.balanced.iter() {
+            if let VariableId::Resource(ResourceId(index)) = variable {
+                if let Some((_, max)) = &mut ranges[index] {
+                    *max = f64::INFINITY;
+                }
+            }
+        }
+
+        // if there is any rule specified for a resource, don't apply the default rule
+        for rule in self.rules.iter() {
+            if let VariableId::Resource(ResourceId(index)) = rule.variable {
+                ranges[index] = None;
+            }
+        }
+
+        ranges
+    }
+
+    /// the total import cap of a resource, zero if it isn't imported
+    fn import_cap(&self, resource: ResourceId) -> f64 {
+        self.imports
+            .iter()
+            .filter(|&&(other, _)| other == resource)
+            .map(|&(_, cap)| cap)
+            .sum()
+    }
+
+    /// a human readable dump of the linear program [Problem::solve] builds, for debugging
+    pub fn describe(&self, world: &World) -> String {
+        let mut description = String::new();
+
+        let (resource_coefficients, recipe_coefficients) = self.objective_coefficients(world);
+
+        description.push_str("maximize\n");
+
+        for (index, coefficient) in resource_coefficients.iter().enumerate() {
+            if *coefficient != 0. {
+                description.push_str(&format!(
+                    "  {:+} * {}\n",
+                    coefficient,
+                    world.name_of_variable(ResourceId(index).variable_id())
+                ));
+            }
+        }
+
+        for (index, coefficient) in recipe_coefficients.iter().enumerate() {
+            if *coefficient != 0. {
+                description.push_str(&format!(
+                    "  {:+} * {}\n",
+                    coefficient,
+                    world.name_of_variable(RecipeId(index).variable_id())
+                ));
+            }
+        }
+
+        if !self.balanced.is_empty() {
+            description.push_str("  +1 * Smallest output\n");
+        }
+
+        description.push_str("\nvariables\n");
+
+        for index in 0..world.resources.len() {
+            description.push_str(&format!(
+                "  {}: free\n",
+                world.name_of_variable(ResourceId(index).variable_id())
+            ));
+        }
+
+        for index in 0..world.recipes.len() {
+            let bound = if self.is_disabled(world, RecipeId(index)) {
+                "= 0 (disabled)".into()
+            } else if self.required.contains(&RecipeId(index)) {
+                format!(">= {} (required)", REQUIRED_RECIPE_MINIMUM)
+            } else {
+                ">= 0".into()
+            };
+
+            description.push_str(&format!(
+                "  {}: {}\n",
+                world.name_of_variable(RecipeId(index).variable_id()),
+                bound
+            ));
+        }
+
+        description.push_str("\nresource balances\n");
+
+        for (index, resource) in world.resources.iter().enumerate() {
+            let terms: Vec<_> = world
+                .recipes
+                .iter()
+                .filter_map(|recipe| {
+                    recipe
+                        .rates
+                        .iter()
+                        .find(|&&(ResourceId(resource), _)| resource == index)
+                        .map(|&(_, rate)| format!("{:+} * Recipe {}", rate, recipe.name))
+                })
+                .collect();
+
+            let sum = if terms.is_empty() {
+                "0".into()
+            } else {
+                terms.join(" ")
+            };
+
+            description.push_str(&format!("  Resource {} = {}\n", resource.name, sum));
+        }
+
+        description.push_str("\nrules\n");
+
+        for rule in self.rules.iter() {
+            let variable = world.name_of_variable(rule.variable);
+            let soft = if rule.soft {
+                format!(" (soft, priority {})", rule.priority)
+            } else {
+                String::new()
+            };
+
+            description.push_str(&match rule.constraint {
+                Constraint::Less(rate) => format!("  {} <= {}{}\n", variable, rate, soft),
+                Constraint::Equal(rate) => format!("  {} = {}{}\n", variable, rate, soft),
+                Constraint::Greater(rate) => format!("  {} >= {}{}\n", variable, rate, soft),
+                Constraint::Unconstrained => format!("  {} unconstrained\n", variable),
+            });
+        }
+
+        for &(a, b, factor) in self.ratios.iter() {
+            description.push_str(&format!(
+                "  {} - {} * {} = 0\n",
+                world.name_of_variable(a),
+                factor,
+                world.name_of_variable(b)
+            ));
+        }
+
+        for &(a, b, fraction) in self.fractions.iter() {
+            description.push_str(&format!(
+                "  {} - {} * {} <= 0\n",
+                world.name_of_variable(a),
+                fraction,
+                world.name_of_variable(b)
+            ));
+        }
+
+        for (terms, value) in self.minimums.iter() {
+            let sum: Vec<_> = terms
+                .iter()
+                .map(|&(variable, weight)| {
+                    format!("{:+} * {}", weight, world.name_of_variable(variable))
+                })
+                .collect();
+
+            description.push_str(&format!("  {} >= {}\n", sum.join(" "), value));
+        }
+
+        for &variable in self.balanced.iter() {
+            description.push_str(&format!(
+                "  {} >= Smallest output\n",
+                world.name_of_variable(variable)
+            ));
+        }
+
+        for (tag, constraint) in self.tag_rules.iter() {
+            let variable = format!("Tag {}", tag);
+
+            description.push_str(&match constraint {
+                Constraint::Less(rate) => format!("  {} <= {}\n", variable, rate),
+                Constraint::Equal(rate) => format!("  {} = {}\n", variable, rate),
+                Constraint::Greater(rate) => format!("  {} >= {}\n", variable, rate),
+                Constraint::Unconstrained => format!("  {} unconstrained\n", variable),
+            });
+        }
+
+        for index in 0..world.resources.len() {
+            if let Some(cap) = world.extraction_cap(ResourceId(index)) {
+                description.push_str(&format!(
+                    "  {} >= {}\n",
+                    world.name_of_variable(ResourceId(index).variable_id()),
+                    -(cap + self.import_cap(ResourceId(index)))
+                ));
+            }
+        }
+
+        description.push_str("\ndefaults\n");
+
+        for (index, range) in self.default_resource_ranges(world).into_iter().enumerate() {
+            let variable = world.name_of_variable(ResourceId(index).variable_id());
+
+            match range {
+                Some((min, max)) if min == max => {
+                    description.push_str(&format!("  {} = {}\n", variable, min))
+                }
+                Some((min, max)) if min == f64::NEG_INFINITY => {
+                    description.push_str(&format!("  {} <= {}\n", variable, max))
+                }
+                Some((min, max)) if max == f64::INFINITY => {
+                    description.push_str(&format!("  {} >= {}\n", variable, min))
+                }
+                Some((min, max)) => {
+                    description.push_str(&format!("  {} <= {} <= {}\n", min, variable, max))
+                }
+                None => (),
+            }
+        }
+
+        description
+    }
+
+    /// solves the problem, returning the factory and the value achieved for the optimizations
+    pub fn solve(&self, world: &World) -> SolveResult {
+        match self.solve_unexplained(world) {
+            Err(response) if response == "Infeasible" => {
+                let mut response = if self.required.is_empty() {
+                    response
+                } else {
+                    self.explain_required(world)
+                };
+
+                let missing: Vec<_> = self
+                    .missing_producers(world)
+                    .into_iter()
+                    .map(|resource| world.name_of_resource(resource))
+                    .collect();
+
+                if !missing.is_empty() {
+                    response.push_str(&format!(", nothing enabled makes {}", missing.join(", ")));
+                }
+
+                Err(response)
+            }
+            result => result,
+        }
+    }
+
+    /// whether a recipe is disabled, either directly or through the recipe it is the amplified variant of
+    pub fn is_disabled(&self, world: &World, recipe: RecipeId) -> bool {
+        self.disabled.contains(&recipe)
+            || world.recipes[recipe.0]
+                .amplifies
+                .is_some_and(|base| self.disabled.contains(&base))
+    }
+
+    /// resources the rules need that can't be made because every recipe making them is disabled
+    ///
+    /// walks back from the resources rules ask a surplus of and the inputs of required recipes,
+    /// through the inputs of the enabled recipes that can't run, stopping at raw and supplied resources
+    pub fn missing_producers(&self, world: &World) -> Vec<ResourceId> {
+        // a tag rule keeping the total at or below zero disables every recipe with the tag
+        let enabled = |recipe: RecipeId| {
+            !self.is_disabled(world, recipe)
+                && !self.tag_rules.iter().any(|(tag, constraint)| {
+                    matches!(constraint, Constraint::Less(rate) | Constraint::Equal(rate) if *rate <= 0.)
+                        && world.recipes[recipe.0].tags.contains(tag)
+                })
+        };
+
+        // resources that can be consumed without making them
+        let raw = world.raw_resources();
+        let supplied = |resource: ResourceId| {
+            raw.contains(&resource)
+                || self.imports.iter().any(|&(import, _)| import == resource)
+                || self.rules.iter().any(|rule| {
+                    rule.variable == resource.variable_id()
+                        && match rule.constraint {
+                            Constraint::Less(_) | Constraint::Unconstrained => true,
+                            Constraint::Equal(rate) | Constraint::Greater(rate) => rate < 0.,
+                        }
+                })
+        };
+
+        let inputs = |recipe: RecipeId| {
+            world.recipes[recipe.0]
+                .rates
+                .iter()
+                .filter(|(_, rate)| *rate < 0.)
+                .map(|&(resource, _)| resource)
+        };
+
+        // grow the set of makeable resources until nothing changes
+        let mut makeable: Vec<_> = (0..world.resources.len())
+            .map(|index| supplied(ResourceId(index)))
+            .collect();
+
+        loop {
+            let mut changed = false;
+
+            for (index, recipe) in world.recipes.iter().enumerate() {
+                if !enabled(RecipeId(index))
+                    || !inputs(RecipeId(index)).all(|resource| makeable[resource.0])
+                {
+                    continue;
+                }
+
+                for &(resource, rate) in recipe.rates.iter() {
+                    if rate > 0. && !makeable[resource.0] {
+                        makeable[resource.0] = true;
+                        changed = true;
+                    }
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let mut stack: Vec<ResourceId> = self
+            .rules
+            .iter()
+            .filter_map(|rule| match (rule.variable, rule.constraint) {
+                (
+                    VariableId::Resource(resource),
+                    Constraint::Equal(rate) | Constraint::Greater(rate),
+                ) if rate > 0. => Some(resource),
+                _ => None,
+            })
+            .chain(self.required.iter().flat_map(|&recipe| inputs(recipe)))
+            .collect();
+
+        let mut visited = vec![false; world.resources.len()];
+        let mut missing = Vec::new();
+
+        while let Some(resource) = stack.pop() {
+            if visited[resource.0] || makeable[resource.0] {
+                continue;
+            }
+
+            visited[resource.0] = true;
+
+            let producers: Vec<_> = world
+                .producers_of(resource)
+                .into_iter()
+                .filter(|&recipe| enabled(recipe))
+                .collect();
+
+            if producers.is_empty() {
+                missing.push(resource);
+            }
+
+            for recipe in producers {
+                stack.extend(inputs(recipe));
+            }
+        }
+
+        missing
+    }
+
+    /// why a problem with required recipes is infeasible, naming the required recipes that can't run
+    fn explain_required(&self, world: &World) -> String {
+        let without = Problem {
+            required: Vec::new(),
+            ..self.clone()
+        };
+
+        // the rules are infeasible on their own
+        if without.solve_unexplained(world).is_err() {
+            return "Infeasible".into();
+        }
+
+        let blocked: Vec<_> = self
+            .required
+            .iter()
+            .filter(|&&recipe| {
+                let alone = Problem {
+                    required: vec![recipe],
+                    ..self.clone()
+                };
+
+                alone.solve_unexplained(world).is_err()
+            })
+            .map(|&recipe| world.name_of_recipe(recipe))
+            .collect();
+
+        if blocked.is_empty() {
+            "The required recipes can't all run together".into()
+        } else {
+            format!(
+                "The required recipes can't run with the rules: {}",
+                blocked.join(", ")
+            )
+        }
+    }
+
+    fn solve_unexplained(&self, world: &World) -> SolveResult {
+        if world.recipes.is_empty() {
+            return Err("The world has no recipes".into());
+        }
+
+        // with every resource defaulting to a net of zero the only solution is an empty factory
+        if self.rules.is_empty()
+            && self.optimizations.is_empty()
+            && self.ratios.is_empty()
+            && self.fractions.is_empty()
+            && self.required.is_empty()
+            && self.imports.is_empty()
+            && self.minimums.is_empty()
+            && self.tag_rules.is_empty()
+            && !self.sink_points
+            && self.balanced.is_empty()
+        {
+            return Err("No rules specified, add an output target".into());
+        }
+
+        // minilp doesn't reject infinities or NaN, it just produces nonsense
+        self.check_finite(world)?;
+        self.check_conflicting_rules(world)?;
+
+        let mut problem = minilp::Problem::new(minilp::OptimizationDirection::Maximize);
+
+        // add all the problem variables
+
+        let (resource_coefficients, recipe_coefficients) = self.objective_coefficients(world);
+
+        let resource_variables: Vec<_> = resource_coefficients
+            .into_iter()
+            .map(|coefficient| problem.add_var(coefficient, (f64::NEG_INFINITY, f64::INFINITY)))
+            .collect();
+
+        // recipes without rates can't do anything, fixing them at zero keeps them out of the model
+        let recipe_variables: Vec<_> = recipe_coefficients
+            .into_iter()
+            .zip(world.recipes.iter())
+            .map(|(coefficient, recipe)| {
+                let bounds = if recipe.rates.is_empty() {
+                    (0., 0.)
+                } else {
+                    (f64::NEG_INFINITY, f64::INFINITY)
+                };
+
+                problem.add_var(coefficient, bounds)
+            })
+            .collect();
+
+        let lp_variable = |variable| match variable {
+            VariableId::Resource(ResourceId(index)) => resource_variables[index],
+            VariableId::Recipe(RecipeId(index)) => recipe_variables[index],
+        };
+
+        // add all the recipe constraints
+        //
+        // the net usage of a resource should be the sum
+        // of all the recipes that produce or consume it
+        // times the rate for each recipe
+
+        let mut resource_recipe_coefficients = vec![Vec::new(); world.resources.len()];
+
+        for (recipe_index, recipe) in world.recipes.iter().enumerate() {
+            let recipe_variable = recipe_variables[recipe_index];
+
+            for &(ResourceId(resource_index), rate) in recipe.rates.iter() {
+                resource_recipe_coefficients[resource_index].push((recipe_variable, rate));
+            }
+        }
+
+        for (resource_index, mut recipe_coefficients) in
+            resource_recipe_coefficients.into_iter().enumerate()
+        {
+            recipe_coefficients.push((resource_variables[resource_index], -1.));
+
+            problem.add_constraint(recipe_coefficients, minilp::ComparisonOp::Eq, 0.)
+        }
+
+        // limit all recipes to be positive
+
+        for &recipe_variable in recipe_variables.iter() {
+            problem.add_constraint([(recipe_variable, 1.)], minilp::ComparisonOp::Ge, 0.);
+        }
+
+        // disabled recipes can't run
+
+        for index in
+            (0..world.recipes.len()).filter(|&index| self.is_disabled(world, RecipeId(index)))
+        {
+            problem.add_constraint(
+                [(recipe_variables[index], 1.)],
+                minilp::ComparisonOp::Eq,
+                0.,
+            );
+        }
+
+        // required recipes must run
+
+        for &RecipeId(index) in self.required.iter() {
+            problem.add_constraint(
+                [(recipe_variables[index], 1.)],
+                minilp::ComparisonOp::Ge,
+                REQUIRED_RECIPE_MINIMUM,
+            );
+        }
+
+        // add user constraints
+
+        for rule in self.rules.iter() {
+            let (operator, rhs) = match rule.constraint {
+                Constraint::Less(rate) => (minilp::ComparisonOp::Le, rate),
+                Constraint::Equal(rate) => (minilp::ComparisonOp::Eq, rate),
+                Constraint::Greater(rate) => (minilp::ComparisonOp::Ge, rate),
+                Constraint::Unconstrained => continue,
+            };
+
+            let mut terms = vec![(lp_variable(rule.variable), 1.)];
+
+            // soft rules get penalized slack, letting the variable move away from the rate in the
+            // direction the constraint forbids
+            if rule.soft {
+                if !matches!(operator, minilp::ComparisonOp::Ge) {
+                    terms.push((problem.add_var(-rule.penalty(), (0., f64::INFINITY)), -1.));
+                }
+
+                if !matches!(operator, minilp::ComparisonOp::Le) {
+                    terms.push((problem.add_var(-rule.penalty(), (0., f64::INFINITY)), 1.));
+                }
+            }
+
+            problem.add_constraint(terms, operator, rhs);
+        }
+
+        // add ratio constraints, a - factor * b = 0
+
+        for &(a, b, factor) in self.ratios.iter() {
+            problem.add_constraint(
+                [(lp_variable(a), 1.), (lp_variable(b), -factor)],
+                minilp::ComparisonOp::Eq,
+                0.,
+            );
+        }
+
+        // add fraction constraints, a - fraction * b <= 0
+
+        for &(a, b, fraction) in self.fractions.iter() {
+            problem.add_constraint(
+                [(lp_variable(a), 1.), (lp_variable(b), -fraction)],
+                minilp::ComparisonOp::Le,
+                0.,
+            );
+        }
+
+        // the smallest balanced output, every balanced output is kept at or above it
+
+        let smallest_output = (!self.balanced.is_empty()).then(|| {
+            let smallest_output = problem.add_var(1., (f64::NEG_INFINITY, f64::INFINITY));
+
+            for &variable in self.balanced.iter() {
+                problem.add_constraint(
+                    [(lp_variable(variable), 1.), (smallest_output, -1.)],
+                    minilp::ComparisonOp::Ge,
+                    0.,
+                );
+            }
+
+            smallest_output
+        });
+
+        // add minimums on weighted sums
+
+        for (terms, value) in self.minimums.iter() {
+            problem.add_constraint(
+                terms
+                    .iter()
+                    .map(|&(variable, weight)| (lp_variable(variable), weight))
+                    .collect::<Vec<_>>(),
+                minilp::ComparisonOp::Ge,
+                *value,
+            );
+        }
+
+        // add tag rules over the sum of all recipes with the tag
+
+        for (tag, constraint) in self.tag_rules.iter() {
+            let (operator, rhs) = match *constraint {
+                Constraint::Less(rate) => (minilp::ComparisonOp::Le, rate),
+                Constraint::Equal(rate) => (minilp::ComparisonOp::Eq, rate),
+                Constraint::Greater(rate) => (minilp::ComparisonOp::Ge, rate),
+                Constraint::Unconstrained => continue,
+            };
+
+            let terms: Vec<_> = world
+                .recipes
+                .iter()
+                .enumerate()
+                .filter(|(_, recipe)| recipe.tags.contains(tag))
+                .map(|(index, _)| (recipe_variables[index], 1.))
+                .collect();
+
+            // an empty sum is zero, which only needs checking
+            if terms.is_empty() {
+                let satisfied = match operator {
+                    minilp::ComparisonOp::Le => 0. <= rhs,
+                    minilp::ComparisonOp::Eq => rhs == 0.,
+                    minilp::ComparisonOp::Ge => 0. >= rhs,
+                };
+
+                if !satisfied {
+                    return Err(format!("No recipe has the tag {}", tag));
+                }
+
+                continue;
+            }
+
+            problem.add_constraint(terms, operator, rhs);
+        }
+
+        // resources with nodes can't be consumed faster than the nodes and imports supply them
+
+        for (index, &resource_variable) in resource_variables.iter().enumerate() {
+            let Some(cap) = world.extraction_cap(ResourceId(index)) else {
+                continue;
+            };
+
+            problem.add_constraint(
+                [(resource_variable, 1.)],
+                minilp::ComparisonOp::Ge,
+                -(cap + self.import_cap(ResourceId(index))),
+            );
+        }
+
+        // add default resource constraints
+
+        for (index, range) in self.default_resource_ranges(world).into_iter().enumerate() {
+            let resource_variable = resource_variables[index];
+
+            match range {
+                Some((min, max)) if min == max => {
+                    problem.add_constraint([(resource_variable, 1.)], minilp::ComparisonOp::Eq, min)
+                }
+                Some((min, max)) => {
+                    if min.is_finite() {
+                        problem.add_constraint(
+                            [(resource_variable, 1.)],
+                            minilp::ComparisonOp::Ge,
+                            min,
+                        );
+                    }
+
+                    if max.is_finite() {
+                        problem.add_constraint(
+                            [(resource_variable, 1.)],
+                            minilp::ComparisonOp::Le,
+                            max,
+                        );
+                    }
+                }
+                None => (),
+            }
+        }
+
+        // solve
+
+        let solution = match problem.solve() {
+            Ok(solution) => solution,
+            Err(error) => {
+                return Err(match error {
+                    minilp::Error::Infeasible => "Infeasible".into(),
+                    minilp::Error::Unbounded => "Unbounded".into(),
+                });
+            }
+        };
+
+        // minilp can report an infinite objective instead of an unbounded error
+        if !solution.objective().is_finite() {
+            return Err("Unbounded".into());
+        }
+
+        let mut factory = Factory::default();
+
+        let zero_threshold = self.zero_threshold.unwrap_or(DEFAULT_ZERO_THRESHOLD);
+
+        for (index, &recipe_variable) in recipe_variables.iter().enumerate() {
+            let unrounded = *solution.var_value(recipe_variable);
+
+            let mut rate =
+                (unrounded * SOLUTION_ROUND_PRECISION).round() / SOLUTION_ROUND_PRECISION;
+
+            if unrounded.abs() < zero_threshold {
+                if !self.keep_zero || unrounded == 0. {
+                    continue;
+                }
+
+                rate = unrounded;
+            } else if rate == 0. {
+                // a real rate below the rounding precision shouldn't disappear
+                rate = unrounded;
+            }
+
+            factory.recipes.push((RecipeId(index), rate));
+        }
+
+        // the weighted sum of the optimizations, sink points and smallest balanced output, excluding any preference nudges
+        let mut objective: f64 = self
+            .optimizations
+            .iter()
+            .map(|&(variable, coefficient)| coefficient * solution.var_value(lp_variable(variable)))
+            .sum();
+
+        if let Some(smallest_output) = smallest_output {
+            objective += solution.var_value(smallest_output);
+        }
+
+        if self.sink_points {
+            objective += world
+                .resources
+                .iter()
+                .zip(resource_variables.iter())
+                .map(|(resource, &variable)| {
+                    resource.points.unwrap_or(0.) * solution.var_value(variable)
+                })
+                .sum::<f64>();
+        }
+
+        // rounding the recipe rates can leave a resource consumed slightly faster than it's made
+        if self.strict_balance {
+            let net_resources = factory.net_resources(world);
+
+            for (index, range) in self.default_resource_ranges(world).into_iter().enumerate() {
+                let Some((min, _)) = range else {
+                    continue;
+                };
+
+                let rate = net_resources.resources[index].0;
+
+                if rate < min - STRICT_BALANCE_TOLERANCE {
+                    return Err(format!(
+                        "{} is short by {} after rounding",
+                        world.name_of_resource(ResourceId(index)),
+                        min - rate
+                    ));
+                }
+            }
+        }
+
+        Ok((factory, objective))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{tests::plate_world, NodeGroup, Purity, Recipe};
+
+    fn rule(variable: VariableId, constraint: Constraint) -> Rule {
+        Rule {
+            variable,
+            constraint,
+            soft: false,
+            priority: 0,
+        }
+    }
+
+    const ORE: VariableId = VariableId::Resource(ResourceId(0));
+    const PLATE: VariableId = VariableId::Resource(ResourceId(2));
+
+    #[test]
+    fn solves_fixed_output() {
+        let problem = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Unconstrained),
+                rule(PLATE, Constraint::Equal(40.)),
+            ],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&plate_world()).unwrap();
+
+        assert_eq!(factory.recipes, vec![(RecipeId(0), 2.), (RecipeId(1), 2.)]);
+    }
+
+    #[test]
+    fn resources_default_to_net_zero() {
+        // smelting twice what the press needs would leave surplus ingots
+        let problem = Problem {
+            rules: vec![
+                rule(PLATE, Constraint::Equal(20.)),
+                rule(RecipeId(0).variable_id(), Constraint::Equal(2.)),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(problem.solve(&plate_world()).err().unwrap(), "Infeasible");
+    }
+
+    #[test]
+    fn contradictory_rules_are_named() {
+        let mut problem = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Unconstrained),
+                rule(PLATE, Constraint::Equal(20.)),
+                rule(PLATE, Constraint::Greater(10.)),
+                rule(PLATE, Constraint::Less(30.)),
+            ],
+            ..Default::default()
+        };
+
+        // rules that overlap are fine
+        assert!(problem.solve(&plate_world()).is_ok());
+
+        problem.rules.push(rule(PLATE, Constraint::Equal(40.)));
+
+        assert_eq!(
+            problem.solve(&plate_world()).err().unwrap(),
+            "The rules on Resource Plate conflict: = 20, >= 10, <= 30, = 40"
+        );
+    }
+
+    #[test]
+    fn recipe_cap_below_target_is_infeasible() {
+        let problem = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Unconstrained),
+                rule(PLATE, Constraint::Equal(40.)),
+                rule(RecipeId(1).variable_id(), Constraint::Less(1.)),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(problem.solve(&plate_world()).err().unwrap(), "Infeasible");
+    }
+
+    #[test]
+    fn power_rule_balances_generators() {
+        // each generator burns a quarter of a miner's coal and needs 3/8 of a water extractor,
+        // netting 66.25 MW after powering those
+        let world = crate::world::load_world("power_world.json").unwrap();
+        let power = world.power.unwrap();
+        let generator = world.recipe_id_of_name("Coal Generator").unwrap();
+
+        let problem = Problem {
+            rules: vec![rule(power.variable_id(), Constraint::Greater(265.))],
+            optimizations: vec![(generator.variable_id(), -1.)],
+            ..Default::default()
+        };
+
+        let (factory, objective) = problem.solve(&world).unwrap();
+
+        assert_eq!(
+            factory.recipes,
+            vec![(RecipeId(0), 4.), (RecipeId(1), 1.), (RecipeId(2), 1.5)]
+        );
+        assert!((objective + 4.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ratio_holds_in_solution() {
+        // a second press recipe that makes plates from ore directly
+        let mut world = plate_world();
+        world.recipes.push(Recipe {
+            name: "Direct Press".into(),
+            rates: vec![(ResourceId(0), -30.), (ResourceId(2), 10.)],
+            ..world.recipes[1].clone()
+        });
+
+        let problem = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Unconstrained),
+                rule(PLATE, Constraint::Equal(60.)),
+            ],
+            ratios: vec![(RecipeId(2).variable_id(), RecipeId(1).variable_id(), 2.)],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&world).unwrap();
+
+        // 30 plates from 1.5 presses and 30 plates from 3 direct presses
+        assert_eq!(
+            factory.recipes,
+            vec![(RecipeId(0), 1.5), (RecipeId(1), 1.5), (RecipeId(2), 3.)]
+        );
+    }
+
+    #[test]
+    fn fractions_bound_one_variable_by_another() {
+        // direct pressing is preferred but may run at most half as many machines as pressing
+        let mut world = plate_world();
+        world.recipes.push(Recipe {
+            name: "Direct Press".into(),
+            rates: vec![(ResourceId(0), -30.), (ResourceId(2), 10.)],
+            ..world.recipes[1].clone()
+        });
+
+        let mut problem = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Unconstrained),
+                rule(PLATE, Constraint::Equal(60.)),
+            ],
+            optimizations: vec![(RecipeId(2).variable_id(), 1.)],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&world).unwrap();
+        assert_eq!(factory.recipes, vec![(RecipeId(2), 6.)]);
+
+        problem.fractions = vec![(RecipeId(2).variable_id(), RecipeId(1).variable_id(), 0.5)];
+
+        let (factory, _) = problem.solve(&world).unwrap();
+        let machines = |recipe| {
+            factory
+                .recipes
+                .iter()
+                .find(|&&(other, _)| other == recipe)
+                .map_or(0., |&(_, rate)| rate)
+        };
+
+        assert!(machines(RecipeId(2)) <= 0.5 * machines(RecipeId(1)) + 1e-6);
+        assert_eq!(machines(RecipeId(2)), 1.2);
+    }
+
+    #[test]
+    fn prunes_marginal_recipes() {
+        // direct pressing is capped, so the last of the ore goes through a tiny smelter and press
+        let mut world = plate_world();
+        world.recipes.push(Recipe {
+            name: "Direct Press".into(),
+            rates: vec![(ResourceId(0), -30.), (ResourceId(2), 30.)],
+            ..world.recipes[1].clone()
+        });
+
+        let problem = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Greater(-33.)),
+                rule(PLATE, Constraint::Unconstrained),
+                rule(RecipeId(2).variable_id(), Constraint::Less(1.)),
+            ],
+            optimizations: vec![(PLATE, 1.)],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&world).unwrap();
+        assert_eq!(
+            factory.recipes,
+            vec![(RecipeId(0), 0.1), (RecipeId(1), 0.1), (RecipeId(2), 1.)]
+        );
+
+        let (factory, _) = problem.prune_marginal(&world, 0.5).unwrap();
+        assert_eq!(factory.recipes, vec![(RecipeId(2), 1.)]);
+
+        // pruning applies the same way when the objectives are solved in tiers
+        let machines = (0..3)
+            .map(|index| (RecipeId(index).variable_id(), -1.))
+            .collect();
+
+        let (factory, _) = problem
+            .prune_marginal_lexicographic(&world, &[vec![(PLATE, 1.)], machines], 0.5)
+            .unwrap();
+        assert_eq!(factory.recipes, vec![(RecipeId(2), 1.)]);
+    }
+
+    #[test]
+    fn non_finite_rate_is_rejected() {
+        let problem = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Unconstrained),
+                rule(PLATE, Constraint::Equal("inf".parse().unwrap())),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            problem.solve(&plate_world()).err().unwrap(),
+            "The rule on Resource Plate has a non-finite rate"
+        );
+    }
+
+    #[test]
+    fn rule_list_resolves_aliases() {
+        let mut world = plate_world();
+        world.resources[2].aliases.push("IronPlate".into());
+
+        let rule_list_json = RuleListJson {
+            rules: vec![RuleJson::Resource {
+                resource: "IronPlate".into(),
+                constraint: Constraint::Equal(20.),
+                soft: false,
+                priority: 0,
+            }],
+            ..Default::default()
+        };
+
+        let rule_list = rule_list_json.into_rule_list(&world).unwrap();
+
+        assert_eq!(rule_list.rules.len(), 1);
+        assert_eq!(rule_list.rules[0].variable, PLATE);
+    }
+
+    #[test]
+    fn pretty_saves_round_trip() {
+        let world = plate_world();
+        let dir = std::env::temp_dir();
+
+        let rule_list = RuleList {
+            rules: vec![
+                rule(ORE, Constraint::Less(0.)),
+                rule(PLATE, Constraint::Equal(20.)),
+            ],
+            optimizations: vec![(RecipeId(0).variable_id(), -1.)],
+            ..Default::default()
+        };
+
+        let path = dir.join("satisfactory_solver_2_rule_list.json");
+        save_rule_list(&world, &rule_list, &path).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains('\n'));
+
+        let loaded = load_rule_list(&world, &path).unwrap();
+        assert_eq!(loaded.rules.len(), 2);
+        assert_eq!(loaded.rules[1].variable, PLATE);
+        assert!(loaded.rules[1].constraint == Constraint::Equal(20.));
+        assert_eq!(loaded.optimizations, rule_list.optimizations);
+
+        let factory = Factory {
+            recipes: vec![(RecipeId(0), 1.), (RecipeId(1), 1.)],
+        };
+
+        let path = dir.join("satisfactory_solver_2_factory.json");
+        crate::factory::save_factory(&world, &factory, &path).unwrap();
+
+        let loaded = crate::factory::load_factory(&world, &path).unwrap();
+        assert_eq!(loaded.recipes, factory.recipes);
+    }
+
+    #[test]
+    fn cache_keys_differ_between_worlds_with_as_many_recipes() {
+        let world = plate_world();
+        let mut renamed = plate_world();
+        renamed.recipes[1].name = "Roll".into();
+
+        let problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            ..Default::default()
+        };
+
+        assert_eq!(problem.cache_key(&world), problem.cache_key(&plate_world()));
+        assert_ne!(problem.cache_key(&world), problem.cache_key(&renamed));
+    }
+
+    #[test]
+    fn baskets_save_and_add_equal_rules() {
+        let world = plate_world();
+        let path = std::env::temp_dir().join("satisfactory_solver_2_basket.json");
+
+        let rule_list = RuleList {
+            baskets: vec![Basket {
+                resources: vec![(ResourceId(1), 3.), (ResourceId(2), 2.)],
+                scale: 10.,
+            }],
+            ..Default::default()
+        };
+
+        save_rule_list(&world, &rule_list, &path).unwrap();
+        let loaded = load_rule_list(&world, &path).unwrap();
+
+        let rules = loaded.all_rules();
+        assert_eq!(rules.len(), 2);
+        assert!(rules[0].constraint == Constraint::Equal(30.));
+        assert_eq!(rules[1].variable, PLATE);
+        assert!(rules[1].constraint == Constraint::Equal(20.));
+    }
+
+    #[test]
+    fn remaps_renamed_variables() {
+        let mut world = plate_world();
+        let path = std::env::temp_dir().join("satisfactory_solver_2_remap.json");
+
+        let rule_list = RuleList {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            optimizations: vec![(PLATE, 1.)],
+            ..Default::default()
+        };
+
+        save_rule_list(&world, &rule_list, &path).unwrap();
+
+        world.resources[2].name = "Iron Plate".into();
+        assert!(matches!(
+            load_rule_list(&world, &path),
+            Err(LoadRuleListError::BadResourceName { .. })
+        ));
+
+        // a recipe with the old name is left alone
+        remap_rule_list(&path, &VariableRef::Recipe("Plate".into()), "Iron Plate").unwrap();
+        assert!(load_rule_list(&world, &path).is_err());
+
+        remap_rule_list(&path, &VariableRef::Resource("Plate".into()), "Iron Plate").unwrap();
+
+        let loaded = load_rule_list(&world, &path).unwrap();
+        assert_eq!(loaded.rules[0].variable, PLATE);
+        assert_eq!(loaded.optimizations, vec![(PLATE, 1.)]);
+    }
+
+    #[test]
+    fn imports_supply_up_to_their_cap() {
+        let mut problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            ..Default::default()
+        };
+
+        // without an import every ingot is smelted
+        let (factory, _) = problem.solve(&plate_world()).unwrap();
+        assert_eq!(factory.recipes, vec![(RecipeId(0), 1.), (RecipeId(1), 1.)]);
+
+        // imported ingots don't count as raw resources so they are used up to the cap
+        problem.imports.push((ResourceId(1), 15.));
+
+        let (factory, _) = problem.solve(&plate_world()).unwrap();
+        assert_eq!(factory.recipes, vec![(RecipeId(0), 0.5), (RecipeId(1), 1.)]);
+    }
+
+    #[test]
+    fn solve_all_keeps_the_base_problem() {
+        // the base's own rule is replaced but its import still applies to each list
+        let base = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(40.))],
+            imports: vec![(ResourceId(1), 15.)],
+            ..Default::default()
+        };
+
+        let lists = [RuleList {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            ..Default::default()
+        }];
+
+        let factories = solve_all(&plate_world(), &base, &lists);
+        assert_eq!(
+            factories[0].as_ref().unwrap().recipes,
+            vec![(RecipeId(0), 0.5), (RecipeId(1), 1.)]
+        );
+    }
+
+    #[test]
+    fn raw_resources_default_to_consumption() {
+        // ore has no rule, it may be consumed because no recipe produces it
+        let problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&plate_world()).unwrap();
+        assert_eq!(factory.recipes, vec![(RecipeId(0), 1.), (RecipeId(1), 1.)]);
+    }
+
+    #[test]
+    fn lexicographic_keeps_earlier_objectives() {
+        // plates can be pressed from ingots or directly from ore at the same ratio
+        let mut world = plate_world();
+        world.recipes.push(Recipe {
+            name: "Direct Press".into(),
+            rates: vec![(ResourceId(0), -30.), (ResourceId(2), 20.)],
+            ..world.recipes[1].clone()
+        });
+
+        let problem = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Greater(-60.)),
+                rule(PLATE, Constraint::Unconstrained),
+            ],
+            ..Default::default()
+        };
+
+        // first make as many plates as possible, then use as few machines as possible
+        let machines = (0..3)
+            .map(|index| (RecipeId(index).variable_id(), -1.))
+            .collect();
+
+        let (factory, values) = problem
+            .solve_lexicographic(&world, &[vec![(PLATE, 1.)], machines])
+            .unwrap();
+
+        assert!((values[0] - 40.).abs() < 1e-6);
+        assert!((values[1] + 2.).abs() < 1e-6);
+        assert_eq!(factory.recipes, vec![(RecipeId(2), 2.)]);
+    }
+
+    #[test]
+    fn empty_problem_is_reported() {
+        assert_eq!(
+            Problem::default().solve(&plate_world()).err().unwrap(),
+            "No rules specified, add an output target"
+        );
+    }
+
+    #[test]
+    fn zero_recipe_world_is_reported() {
+        let world = World {
+            recipes: Vec::new(),
+            ..plate_world()
+        };
+
+        let problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            problem.solve(&world).err().unwrap(),
+            "The world has no recipes"
+        );
+    }
+
+    #[test]
+    fn unconstrained_output_is_unbounded() {
+        let problem = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Unconstrained),
+                rule(PLATE, Constraint::Unconstrained),
+            ],
+            optimizations: vec![(PLATE, 1.)],
+            ..Default::default()
+        };
+
+        assert_eq!(problem.solve(&plate_world()).err().unwrap(), "Unbounded");
+    }
+
+    #[test]
+    fn optimizations_on_the_same_variable_accumulate() {
+        // the weights sum to a positive coefficient, so plates are maximized
+        let problem = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Unconstrained),
+                rule(PLATE, Constraint::Less(40.)),
+            ],
+            optimizations: vec![(PLATE, 2.), (PLATE, -1.)],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&plate_world()).unwrap();
+
+        assert_eq!(factory.recipes, vec![(RecipeId(0), 2.), (RecipeId(1), 2.)]);
+    }
+
+    #[test]
+    fn tag_rule_caps_tagged_recipes() {
+        // two smelter recipes and a wasteful untagged one to make up the difference
+        let mut world = plate_world();
+        world.recipes[0].tags = vec!["Smelter".into()];
+
+        for (name, ore, tags) in [
+            ("Alt Smelt", -20., vec!["Smelter".to_string()]),
+            ("Scrap Ingot", -60., Vec::new()),
+        ] {
+            world.recipes.push(Recipe {
+                name: name.into(),
+                tags,
+                rates: vec![(ResourceId(0), ore), (ResourceId(1), 30.)],
+                preference: 0.,
+                note: None,
+                building: None,
+                aliases: Vec::new(),
+                tier: None,
+                amplifies: None,
+            });
+        }
+
+        let problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(40.))],
+            tag_rules: vec![("Smelter".into(), Constraint::Less(1.))],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&world).unwrap();
+
+        assert_eq!(
+            factory.recipes,
+            vec![(RecipeId(1), 2.), (RecipeId(2), 1.), (RecipeId(3), 1.)]
+        );
+    }
+
+    #[test]
+    fn loss_without_recipe() {
+        let problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            ..Default::default()
+        };
+
+        let loss_without = |world: &World, recipe| {
+            let (factory, _) = problem.solve(world).unwrap();
+            problem.loss_without(world, &factory, recipe, |without| without.solve(world))
+        };
+
+        // pressing is the only way to make plates
+        let world = plate_world();
+        assert_eq!(loss_without(&world, RecipeId(1)), Ok(None));
+
+        // direct pressing makes the same plates from twice the ore
+        let mut world = plate_world();
+        world.recipes.push(Recipe {
+            name: "Direct Press".into(),
+            rates: vec![(ResourceId(0), -30.), (ResourceId(2), 10.)],
+            ..world.recipes[1].clone()
+        });
+
+        let loss = loss_without(&world, RecipeId(1)).unwrap().unwrap();
+        assert!((loss - 30.).abs() < 1e-6);
+
+        let loss = loss_without(&world, RecipeId(2)).unwrap().unwrap();
+        assert!(loss.abs() < 1e-6);
+
+        // the loss is measured from the factory given, here one that already presses directly
+        let factory = Factory {
+            recipes: vec![(RecipeId(2), 2.)],
+        };
+        let loss = problem
+            .loss_without(&world, &factory, RecipeId(1), |without| {
+                without.solve(&world)
+            })
+            .unwrap()
+            .unwrap();
+        assert!(loss.abs() < 1e-6);
+    }
+
+    #[test]
+    fn empty_recipes_are_never_used() {
+        // a preference would otherwise make the solver run the empty recipe without bound
+        let mut world = plate_world();
+        world.recipes.push(Recipe {
+            name: "Nothing".into(),
+            rates: Vec::new(),
+            preference: 1.,
+            ..world.recipes[0].clone()
+        });
+
+        assert_eq!(world.empty_recipes(), vec![RecipeId(2)]);
+
+        let problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&world).unwrap();
+
+        assert_eq!(factory.recipes, vec![(RecipeId(0), 1.), (RecipeId(1), 1.)]);
+    }
+
+    #[test]
+    fn nodes_cap_extraction() {
+        // a pure node is worth two normal nodes of 15 ore each
+        let mut world = plate_world();
+        world.node_rate = 15.;
+        world.nodes.push(NodeGroup {
+            resource: ResourceId(0),
+            purity: Purity::Pure,
+            count: 1,
+        });
+
+        let mut problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            ..Default::default()
+        };
+
+        assert!(problem.solve(&world).is_ok());
+
+        problem.rules = vec![rule(PLATE, Constraint::Equal(40.))];
+        assert_eq!(problem.solve(&world).err().unwrap(), "Infeasible");
+    }
+
+    #[test]
+    fn zero_threshold_keeps_small_rates() {
+        // a press running 2e-7 machines rounds to zero
+        let mut problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(4e-6))],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&plate_world()).unwrap();
+        assert!(factory.recipes.is_empty());
+
+        problem.zero_threshold = Some(1e-9);
+
+        let (factory, _) = problem.solve(&plate_world()).unwrap();
+        assert_eq!(factory.recipes.len(), 2);
+        assert!((factory.recipes[1].1 - 2e-7).abs() < 1e-12);
+    }
+
+    #[test]
+    fn strict_balance_rejects_rounded_deficits() {
+        // a third of a smelter rounds down, leaving the press slightly short of ingots
+        let mut world = plate_world();
+        world.recipes[0].rates = vec![(ResourceId(0), -1.), (ResourceId(1), 3.)];
+        world.recipes[1].rates = vec![(ResourceId(1), -1.), (ResourceId(2), 1.)];
+
+        let mut problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(1.))],
+            ..Default::default()
+        };
+
+        assert!(problem.solve(&world).is_ok());
+
+        problem.strict_balance = true;
+
+        let response = problem.solve(&world).err().unwrap();
+        assert!(response.starts_with("Ingot is short"), "{}", response);
+    }
+
+    #[test]
+    fn required_recipes_run() {
+        let mut world = plate_world();
+        world.recipes.push(Recipe {
+            name: "Other Press".into(),
+            ..world.recipes[1].clone()
+        });
+
+        let mut problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            required: vec![RecipeId(2)],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&world).unwrap();
+        assert!(factory
+            .recipes
+            .iter()
+            .any(|&(recipe, machines)| recipe == RecipeId(2) && machines > 0.));
+
+        problem.disabled.push(RecipeId(2));
+
+        assert_eq!(
+            problem.solve(&world).err().unwrap(),
+            "The required recipes can't run with the rules: Other Press"
+        );
+    }
+
+    #[test]
+    fn solves_with_only_a_required_recipe() {
+        // scrapping plates lets the required press run without an output target
+        let mut world = plate_world();
+        world.recipes.push(Recipe {
+            name: "Scrap".into(),
+            rates: vec![(ResourceId(2), -20.)],
+            ..world.recipes[1].clone()
+        });
+
+        let problem = Problem {
+            required: vec![RecipeId(1)],
+            ..Default::default()
+        };
+
+        let (factory, _) = problem.solve(&world).unwrap();
+        assert!(factory
+            .recipes
+            .iter()
+            .any(|&(recipe, machines)| recipe == RecipeId(1) && machines > 0.));
+    }
+
+    #[test]
+    fn infeasibility_names_resources_without_producers() {
+        let problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            disabled: vec![RecipeId(0)],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            problem.solve(&plate_world()).err().unwrap(),
+            "Infeasible, nothing enabled makes Ingot"
+        );
+    }
+
+    #[test]
+    fn soft_rules_give_way() {
+        // a single press can only make 20 plates
+        let soft_plates = Rule {
+            soft: true,
+            ..rule(PLATE, Constraint::Equal(30.))
+        };
+
+        let mut problem = Problem {
+            rules: vec![
+                rule(RecipeId(1).variable_id(), Constraint::Less(1.)),
+                rule(PLATE, Constraint::Equal(30.)),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(problem.solve(&plate_world()).err().unwrap(), "Infeasible");
+
+        problem.rules[1] = soft_plates;
+
+        let (factory, _) = problem.solve(&plate_world()).unwrap();
+        let plates = factory.net_resources(&plate_world()).resources[2].0;
+
+        assert_eq!(plates, 20.);
+        assert_eq!(soft_plates.violation(plates), 10.);
+    }
+
+    #[test]
+    fn ties_are_broken_deterministically() {
+        // two identical presses, either could make all the plates
+        let mut world = plate_world();
+        world.recipes.push(Recipe {
+            name: "Other Press".into(),
+            ..world.recipes[1].clone()
+        });
+
+        let problem = Problem {
+            rules: vec![rule(PLATE, Constraint::Equal(20.))],
+            ..Default::default()
+        };
+
+        let (first, _) = problem.solve(&world).unwrap();
+        let (second, _) = problem.solve(&world).unwrap();
+
+        assert_eq!(first.recipes, second.recipes);
+        assert_eq!(first.recipes, vec![(RecipeId(0), 1.), (RecipeId(1), 1.)]);
+    }
+
+    #[test]
+    fn higher_priority_soft_rules_are_broken_last() {
+        let world = plate_world();
+
+        // 30 plates need 45 ore, so one of the rules has to give way
+        let solve = |plate_priority, ore_priority| {
+            let problem = Problem {
+                rules: vec![
+                    Rule {
+                        soft: true,
+                        priority: plate_priority,
+                        ..rule(PLATE, Constraint::Greater(30.))
+                    },
+                    Rule {
+                        soft: true,
+                        priority: ore_priority,
+                        ..rule(ORE, Constraint::Greater(-30.))
+                    },
+                ],
+                ..Default::default()
+            };
+
+            let (factory, _) = problem.solve(&world).unwrap();
+            let net_resources = factory.net_resources(&world);
+            (net_resources.resources[0].0, net_resources.resources[2].0)
+        };
+
+        // at equal priority the smaller violation wins, falling 10 plates short instead of 15 ore over
+        let (ore, plates) = solve(0, 0);
+        assert!((ore + 30.).abs() < 1e-6);
+        assert!((plates - 20.).abs() < 1e-6);
+
+        let (ore, plates) = solve(1, 0);
+        assert!((ore + 45.).abs() < 1e-6);
+        assert!((plates - 30.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn maximize_min_balances_outputs() {
+        let world = plate_world();
+        let ingot = ResourceId(1);
+
+        let mut problem = Problem {
+            rules: vec![rule(ORE, Constraint::Greater(-60.))],
+            ..Default::default()
+        };
+        problem.maximize_min(vec![ingot.variable_id(), PLATE]);
+
+        let (factory, objective) = problem.solve(&world).unwrap();
+        let net_resources = factory.net_resources(&world);
+
+        // 60 ingots a minute split so the surplus ingots match the plates pressed from the rest
+        assert!((net_resources.resources[ingot.0].0 - 24.).abs() < 1e-6);
+        assert!((net_resources.resources[2].0 - 24.).abs() < 1e-6);
+        assert!((objective - 24.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sink_points_objective() {
+        // plates take fewer machines for the same ore, but rods are worth more points
+        let mut world = plate_world();
+        world.resources[1].name = "Rod".into();
+        world.resources[1].points = Some(20.);
+        world.resources[2].points = Some(2.);
+        world.recipes = vec![
+            Recipe {
+                name: "Plate".into(),
+                rates: vec![(ResourceId(0), -30.), (ResourceId(2), 30.)],
+                ..world.recipes[0].clone()
+            },
+            Recipe {
+                name: "Rod".into(),
+                rates: vec![(ResourceId(0), -10.), (ResourceId(1), 5.)],
+                ..world.recipes[0].clone()
+            },
+        ];
+
+        let fewest_machines = Problem {
+            rules: vec![
+                rule(ORE, Constraint::Equal(-60.)),
+                rule(
+                    VariableId::Resource(ResourceId(1)),
+                    Constraint::Unconstrained,
+                ),
+                rule(PLATE, Constraint::Unconstrained),
+            ],
+            optimizations: vec![
+                (VariableId::Recipe(RecipeId(0)), -1.),
+                (VariableId::Recipe(RecipeId(1)), -1.),
+            ],
+            ..Default::default()
+        };
+
+        let (factory, _) = fewest_machines.solve(&world).unwrap();
+        assert_eq!(factory.recipes, vec![(RecipeId(0), 2.)]);
+
+        let most_points = Problem {
+            rules: vec![rule(ORE, Constraint::Equal(-60.))],
+            sink_points: true,
+            ..Default::default()
+        };
+
+        let (factory, objective) = most_points.solve(&world).unwrap();
+        assert_eq!(factory.recipes, vec![(RecipeId(1), 6.)]);
+        assert!((objective - 600.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn lexicographic_tiers_with_sink_points() {
+        // the first stage's objective includes the points of sunk plates,
+        // which must not become a floor on the plates alone
+        let mut world = plate_world();
+        world.resources[2].points = Some(2.);
+
+        let problem = Problem {
+            rules: vec![rule(ORE, Constraint::Equal(-60.))],
+            sink_points: true,
+            ..Default::default()
+        };
+
+        let machines = vec![
+            (RecipeId(0).variable_id(), -1.),
+            (RecipeId(1).variable_id(), -1.),
+        ];
+
+        let (factory, values) = problem
+            .solve_lexicographic(&world, &[vec![(PLATE, 1.)], machines])
+            .unwrap();
+
+        assert!((values[0] - 40.).abs() < 1e-6);
+        assert!((values[1] + 4.).abs() < 1e-6);
+        assert_eq!(factory.recipes, vec![(RecipeId(0), 2.), (RecipeId(1), 2.)]);
     }
 }
@@ -0,0 +1,196 @@
+use std::{io::Write, path::PathBuf};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// how many recently opened worlds to remember
+pub const MAX_RECENT_WORLDS: usize = 8;
+
+/// how many decimals machine counts and rates are shown with unless the user picks otherwise
+pub const DEFAULT_PRECISION: usize = 3;
+
+/// the most decimals the user can pick
+pub const MAX_PRECISION: usize = 6;
+
+/// settings remembered between sessions
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    /// paths of recently opened world files, most recent first
+    #[serde(default)]
+    pub recent_worlds: Vec<String>,
+    #[serde(default)]
+    pub theme: Theme,
+    #[serde(default)]
+    pub time_base: TimeBase,
+    /// how many decimals machine counts and rates are shown with
+    #[serde(default = "default_precision")]
+    pub precision: usize,
+    /// separate thousands with commas, like "9,600"
+    #[serde(default = "default_thousands_separator")]
+    pub thousands_separator: bool,
+    /// keep the window above other windows
+    #[serde(default)]
+    pub always_on_top: bool,
+    /// the size of the window's contents when it was last closed
+    #[serde(default)]
+    pub window_size: Option<[f32; 2]>,
+    /// where the window's top left corner was when it was last closed
+    #[serde(default)]
+    pub window_position: Option<[f32; 2]>,
+}
+
+fn default_precision() -> usize {
+    DEFAULT_PRECISION
+}
+
+fn default_thousands_separator() -> bool {
+    true
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            recent_worlds: Vec::new(),
+            theme: Theme::default(),
+            time_base: TimeBase::default(),
+            precision: DEFAULT_PRECISION,
+            thousands_separator: true,
+            always_on_top: false,
+            window_size: None,
+            window_position: None,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// the unit of time rates are displayed and entered in
+///
+/// worlds and the solver always work per minute, this only changes what the user sees
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeBase {
+    #[default]
+    PerMinute,
+    PerSecond,
+}
+
+impl TimeBase {
+    /// converts a per minute rate into this time base
+    pub fn to_display(self, per_minute: f64) -> f64 {
+        match self {
+            TimeBase::PerMinute => per_minute,
+            TimeBase::PerSecond => per_minute / 60.,
+        }
+    }
+
+    /// converts a rate in this time base back to per minute
+    pub fn from_display(self, rate: f64) -> f64 {
+        match self {
+            TimeBase::PerMinute => rate,
+            TimeBase::PerSecond => rate * 60.,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            TimeBase::PerMinute => "/min",
+            TimeBase::PerSecond => "/sec",
+        }
+    }
+}
+
+impl Config {
+    /// moves a world path to the front of the recent worlds
+    pub fn add_recent_world(&mut self, path: String) {
+        self.recent_worlds.retain(|recent| *recent != path);
+        self.recent_worlds.insert(0, path);
+        self.recent_worlds.truncate(MAX_RECENT_WORLDS);
+    }
+}
+
+/// the path of the config file within the os config directory
+fn config_path() -> Option<PathBuf> {
+    let config_dir = if cfg!(target_os = "windows") {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(std::env::var_os("HOME")?).join("Library/Application Support")
+    } else {
+        match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        }
+    };
+
+    Some(config_dir.join("satisfactory_solver_2").join("config.json"))
+}
+
+/// loads the config, falling back to the default if there is none
+///
+/// recent worlds that no longer exist are dropped
+pub fn load_config() -> Config {
+    let Some(path) = config_path() else {
+        return Config::default();
+    };
+
+    let Ok(file) = std::fs::File::open(path) else {
+        return Config::default();
+    };
+
+    let mut config: Config = match serde_json::from_reader(std::io::BufReader::new(file)) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!("failed to parse config file: {:?}", err);
+            return Config::default();
+        }
+    };
+
+    config
+        .recent_worlds
+        .retain(|path| std::path::Path::new(path).exists());
+
+    config
+}
+
+pub fn save_config(config: &Config) {
+    let Some(path) = config_path() else {
+        warn!("could not find a config directory");
+        return;
+    };
+
+    if let Some(dir) = path.parent() {
+        if let Err(err) = std::fs::create_dir_all(dir) {
+            warn!("failed to create config directory: {:?}", err);
+            return;
+        }
+    }
+
+    let mut file = match std::fs::File::create(path) {
+        Ok(file) => file,
+        Err(err) => {
+            warn!("failed to open config file: {:?}", err);
+            return;
+        }
+    };
+
+    if let Err(err) = file.write_all(
+        serde_json::to_string(config)
+            .expect("Failed to convert to json")
+            .as_bytes(),
+    ) {
+        warn!("failed to write to config file: {:?}", err);
+    }
+}
+
+/// loads the config, applies a change and saves it again
+///
+/// reloading first keeps changes made elsewhere in the app
+pub fn update_config(change: impl FnOnce(&mut Config)) {
+    let mut config = load_config();
+    change(&mut config);
+    save_config(&config);
+}
@@ -1,16 +1,36 @@
 use std::io::Write;
 
-use log::warn;
 use serde::{Deserialize, Serialize};
 
-use crate::world::{RecipeId, ResourceId, World};
+use crate::{
+    builder::SOLUTION_ROUND_PRECISION,
+    save::{write_atomic, SaveError},
+    world::{RecipeId, ResourceId, VariableId, World},
+};
 
 #[derive(Default, Clone)]
 pub struct Factory {
     pub recipes: Vec<(RecipeId, f64)>,
 }
 
+/// a resource in a [Factory::production_tree] with the recipes producing it
+pub struct ProductionNode {
+    pub resource: ResourceId,
+    /// how much of the resource this node stands for
+    pub rate: f64,
+    /// empty for raw resources and for resources already expanded elsewhere in the tree
+    pub producers: Vec<ProducerNode>,
+}
+
+/// a recipe producing the resource of a [ProductionNode], with the resources it consumes
+pub struct ProducerNode {
+    pub recipe: RecipeId,
+    pub machines: f64,
+    pub inputs: Vec<ProductionNode>,
+}
+
 /// computed net resources from a [Factory]
+#[derive(Clone)]
 pub struct NetResources {
     pub resources: Vec<(f64, Vec<(RecipeId, f64)>)>,
 }
@@ -33,37 +53,389 @@ impl Factory {
 
         NetResources { resources }
     }
+
+    /// the net rate of a resource or the machines of a recipe, given the factory's net resources
+    pub fn value_of(&self, net_resources: &NetResources, variable: VariableId) -> f64 {
+        match variable {
+            VariableId::Resource(resource) => net_resources.resources[resource.0].0,
+            VariableId::Recipe(recipe) => self
+                .recipes
+                .iter()
+                .find(|&&(other, _)| other == recipe)
+                .map_or(0., |&(_, rate)| rate),
+        }
+    }
+
+    /// writes each recipe as a json object on its own line, like `{"recipe":"Smelt","machines":2.0}`,
+    /// so scripts can process large factories a line at a time
+    pub fn write_jsonl(&self, world: &World, mut writer: impl Write) -> std::io::Result<()> {
+        for &(recipe, machines) in self.recipes.iter() {
+            let line = RecipeLineJson {
+                recipe: world.name_of_recipe(recipe),
+                machines,
+            };
+
+            serde_json::to_writer(&mut writer, &line)?;
+            writer.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+
+    /// the machines as plain text, a line like `4x Smelt` per recipe with machine counts rounded up
+    pub fn machine_list(&self, world: &World) -> String {
+        let mut text = String::new();
+
+        for &(recipe, rate) in self.recipes.iter() {
+            text.push_str(&format!(
+                "{}x {}\n",
+                rate.ceil(),
+                world.name_of_recipe(recipe)
+            ));
+        }
+
+        text
+    }
+
+    /// reads a factory from lines like `4x Smelt`, as written by [Factory::machine_list]
+    ///
+    /// blank lines, headings ending in `:` and indented lines are skipped,
+    /// so the inputs and outputs of a copied factory don't need removing first
+    pub fn from_text(world: &World, text: &str) -> Result<Factory, Vec<ParseFactoryError>> {
+        let mut factory = Factory::default();
+        let mut errors = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+
+            if line.trim().is_empty()
+                || line.starts_with(char::is_whitespace)
+                || line.ends_with(':')
+            {
+                continue;
+            }
+
+            let machines = line.split_once(' ').and_then(|(count, recipe_name)| {
+                let count = count.strip_suffix(['x', 'X'])?.parse::<f64>().ok()?;
+                (count.is_finite() && count >= 0.).then_some((count, recipe_name.trim()))
+            });
+
+            let Some((count, recipe_name)) = machines else {
+                errors.push(ParseFactoryError::BadLine { line: line_number });
+                continue;
+            };
+
+            match world.recipe_id_of_name(recipe_name) {
+                Some(recipe) => factory.recipes.push((recipe, count)),
+                None => errors.push(ParseFactoryError::BadRecipeName {
+                    line: line_number,
+                    recipe_name: recipe_name.into(),
+                }),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(factory)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// the flows between recipes and resources in SankeyMatic's input format, a line like `Smelt [30] Ingot` per flow
+    pub fn to_sankey(&self, world: &World) -> String {
+        let mut sankey = String::new();
+
+        for (resource_index, (_, contributors)) in
+            self.net_resources(world).resources.iter().enumerate()
+        {
+            let resource_name = world.name_of_resource(ResourceId(resource_index));
+
+            for &(recipe, rate) in contributors.iter() {
+                let rate = (rate * SOLUTION_ROUND_PRECISION).round() / SOLUTION_ROUND_PRECISION;
+
+                if rate == 0. {
+                    continue;
+                }
+
+                let recipe_name = world.name_of_recipe(recipe);
+
+                // producers flow into the resource and consumers flow out of it
+                let (from, to) = if rate > 0. {
+                    (recipe_name, resource_name)
+                } else {
+                    (resource_name, recipe_name)
+                };
+
+                sankey.push_str(&format!("{} [{}] {}\n", from, rate.abs(), to));
+            }
+        }
+
+        sankey
+    }
+
+    /// the number of machines running all the recipes, rounding each recipe up to whole machines if `round_up`
+    pub fn total_machines(&self, round_up: bool) -> f64 {
+        self.recipes
+            .iter()
+            .map(|&(_, rate)| if round_up { rate.ceil() } else { rate })
+            .sum()
+    }
+
+    /// the total rate raw resources are consumed at
+    pub fn raw_intake(&self, world: &World) -> f64 {
+        let net_resources = self.net_resources(world);
+
+        world
+            .raw_resources()
+            .into_iter()
+            .map(|resource| (-net_resources.resources[resource.0].0).max(0.))
+            .sum()
+    }
+
+    /// the power consumed by the machines, not counting what generators make, zero if the world has no power
+    pub fn power_usage(&self, world: &World) -> f64 {
+        let Some(power) = world.power else {
+            return 0.;
+        };
+
+        self.recipes
+            .iter()
+            .flat_map(|&(recipe, machines)| {
+                world.recipes[recipe.0]
+                    .rates
+                    .iter()
+                    .filter(move |&&(resource, rate)| resource == power && rate < 0.)
+                    .map(move |&(_, rate)| -rate * machines)
+            })
+            .sum()
+    }
+
+    /// the floor area of all the machines, or none if the world has no footprints for their buildings
+    ///
+    /// machines are rounded up since a fraction of a building takes up the whole building's space
+    pub fn total_footprint(&self, world: &World) -> Option<f64> {
+        let mut total = None;
+
+        for &(recipe, rate) in self.recipes.iter() {
+            let footprint = world.recipes[recipe.0]
+                .building
+                .as_ref()
+                .and_then(|building| world.footprints.get(building));
+
+            if let Some(footprint) = footprint {
+                *total.get_or_insert(0.) += rate.ceil() * footprint;
+            }
+        }
+
+        total
+    }
+
+    /// the recipes producing a resource, the resources they consume, and recursively how those are produced
+    ///
+    /// each resource is only expanded the first time it appears so that cycles terminate
+    pub fn production_tree(&self, world: &World, root: ResourceId) -> ProductionNode {
+        let rate = self.net_resources(world).resources[root.0].0;
+        let mut visited = Vec::new();
+
+        self.production_node(world, root, rate, &mut visited)
+    }
+
+    fn production_node(
+        &self,
+        world: &World,
+        resource: ResourceId,
+        rate: f64,
+        visited: &mut Vec<ResourceId>,
+    ) -> ProductionNode {
+        let mut node = ProductionNode {
+            resource,
+            rate,
+            producers: Vec::new(),
+        };
+
+        if visited.contains(&resource) {
+            return node;
+        }
+
+        visited.push(resource);
+
+        for &(recipe, machines) in self.recipes.iter() {
+            let rates = &world.recipes[recipe.0].rates;
+
+            if !rates
+                .iter()
+                .any(|&(output, rate)| output == resource && rate > 0.)
+            {
+                continue;
+            }
+
+            let inputs = rates
+                .iter()
+                .filter(|&&(_, rate)| rate < 0.)
+                .map(|&(input, rate)| self.production_node(world, input, -rate * machines, visited))
+                .collect();
+
+            node.producers.push(ProducerNode {
+                recipe,
+                machines,
+                inputs,
+            });
+        }
+
+        node
+    }
+
+    /// each recipe in either factory with its rate in this factory and in the other
+    ///
+    /// recipes missing from a factory have a rate of 0
+    pub fn diff(&self, other: &Factory) -> Vec<(RecipeId, f64, f64)> {
+        let rate_in = |factory: &Factory, recipe: RecipeId| {
+            factory
+                .recipes
+                .iter()
+                .find(|&&(other_recipe, _)| other_recipe == recipe)
+                .map_or(0., |&(_, rate)| rate)
+        };
+
+        let mut diff: Vec<_> = self
+            .recipes
+            .iter()
+            .map(|&(recipe, rate)| (recipe, rate, rate_in(other, recipe)))
+            .collect();
+
+        for &(recipe, rate) in other.recipes.iter() {
+            if !diff.iter().any(|&(existing, _, _)| existing == recipe) {
+                diff.push((recipe, 0., rate));
+            }
+        }
+
+        diff
+    }
+}
+
+impl NetResources {
+    /// the net resources of several factories together, summing each resource's rate
+    /// and keeping every factory's contributors
+    pub fn combine(others: &[NetResources]) -> NetResources {
+        let length = others
+            .iter()
+            .map(|other| other.resources.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut resources = vec![(0., Vec::new()); length];
+
+        for other in others.iter() {
+            for ((rate, contributors), (other_rate, other_contributors)) in
+                resources.iter_mut().zip(other.resources.iter())
+            {
+                *rate += other_rate;
+                contributors.extend(other_contributors.iter().copied());
+            }
+        }
+
+        NetResources { resources }
+    }
+
+    /// the recipes contributing to a resource, producers first then consumers,
+    /// each ordered from the largest flow to the smallest with ties broken by recipe name
+    pub fn sorted_contributors(&self, world: &World, resource: ResourceId) -> Vec<(RecipeId, f64)> {
+        let mut contributors = self.resources[resource.0].1.clone();
+
+        contributors.sort_by(|&(recipe_a, rate_a), &(recipe_b, rate_b)| {
+            (rate_b > 0.)
+                .cmp(&(rate_a > 0.))
+                .then(rate_b.abs().total_cmp(&rate_a.abs()))
+                .then_with(|| {
+                    world
+                        .name_of_recipe(recipe_a)
+                        .cmp(world.name_of_recipe(recipe_b))
+                })
+        });
+
+        contributors
+    }
+
+    /// resources both produced and consumed within the factory whose net is further from zero than `tolerance`
+    ///
+    /// resources that are only produced or only consumed are the factory's intended outputs and inputs
+    pub fn unbalanced(&self, tolerance: f64) -> Vec<(ResourceId, f64)> {
+        self.resources
+            .iter()
+            .enumerate()
+            .filter(|(_, (rate, recipes))| {
+                rate.abs() > tolerance
+                    && recipes.iter().any(|&(_, rate)| rate > 0.)
+                    && recipes.iter().any(|&(_, rate)| rate < 0.)
+            })
+            .map(|(index, &(rate, _))| (ResourceId(index), rate))
+            .collect()
+    }
+}
+
+/// a line of [Factory::write_jsonl]
+#[derive(Serialize)]
+struct RecipeLineJson<'a> {
+    recipe: &'a str,
+    machines: f64,
 }
 
 #[derive(Default, Serialize, Deserialize)]
-struct FactoryJson {
-    recipes: Vec<(String, f64)>,
+pub struct FactoryJson {
+    pub recipes: Vec<(String, f64)>,
 }
 
-pub fn save_factory(world: &World, factory: &Factory, path: impl AsRef<std::path::Path>) {
-    let mut factory_json = FactoryJson::default();
+impl FactoryJson {
+    pub fn new(world: &World, factory: &Factory) -> Self {
+        let mut factory_json = FactoryJson::default();
+
+        for &(recipe, rate) in factory.recipes.iter() {
+            let recipe_name = world.name_of_recipe(recipe).into();
 
-    for &(recipe, rate) in factory.recipes.iter() {
-        let recipe_name = world.name_of_recipe(recipe).into();
+            factory_json.recipes.push((recipe_name, rate));
+        }
 
-        factory_json.recipes.push((recipe_name, rate));
+        factory_json
     }
 
-    let mut file = match std::fs::File::create(path) {
-        Ok(file) => file,
-        Err(err) => {
-            warn!("failed to open factory file: {:?}", err);
-            return;
+    /// points every use of the recipe `old_name` at `new_name` instead
+    pub fn remap(&mut self, old_name: &str, new_name: &str) {
+        for (recipe_name, _) in self.recipes.iter_mut() {
+            if recipe_name == old_name {
+                *recipe_name = new_name.into();
+            }
+        }
+    }
+
+    /// converts recipe names back into ids within a world
+    pub fn into_factory(self, world: &World) -> Result<Factory, LoadFactoryError> {
+        let mut factory = Factory::default();
+
+        for (recipe_name, rate) in self.recipes {
+            let Some(recipe) = world.recipe_id_of_name(&recipe_name) else {
+                return Err(LoadFactoryError::BadRecipeName { recipe_name });
+            };
+
+            factory.recipes.push((recipe, rate));
         }
-    };
 
-    if let Err(err) = file.write_all(
-        serde_json::to_string(&factory_json)
+        Ok(factory)
+    }
+}
+
+pub fn save_factory(
+    world: &World,
+    factory: &Factory,
+    path: impl AsRef<std::path::Path>,
+) -> Result<(), SaveError> {
+    let factory_json = FactoryJson::new(world, factory);
+
+    write_atomic(
+        path,
+        serde_json::to_string_pretty(&factory_json)
             .expect("Failed to convert to json")
             .as_bytes(),
-    ) {
-        warn!("failed to write to factory file: {:?}", err);
-    }
+    )
 }
 
 #[derive(Debug)]
@@ -73,6 +445,19 @@ pub enum LoadFactoryError {
     BadRecipeName { recipe_name: String },
 }
 
+/// why a line of a plain text factory couldn't be read, lines are numbered from one
+#[derive(Debug)]
+pub enum ParseFactoryError {
+    /// the line isn't a machine count followed by a recipe name, like `4x Smelt`
+    BadLine {
+        line: usize,
+    },
+    BadRecipeName {
+        line: usize,
+        recipe_name: String,
+    },
+}
+
 pub fn load_factory(
     world: &World,
     path: impl AsRef<std::path::Path>,
@@ -87,15 +472,91 @@ pub fn load_factory(
         Err(err) => return Err(LoadFactoryError::JsonError(err)),
     };
 
-    let mut factory = Factory::default();
+    factory_json.into_factory(world)
+}
+
+/// rewrites a saved factory so every use of the recipe `old_name` uses `new_name` instead,
+/// for migrating factories after a world renames a recipe
+pub fn remap_factory(
+    path: impl AsRef<std::path::Path>,
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), LoadFactoryError> {
+    let file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(err) => return Err(LoadFactoryError::IoError(err)),
+    };
+
+    let mut factory_json: FactoryJson = match serde_json::from_reader(std::io::BufReader::new(file))
+    {
+        Ok(factory) => factory,
+        Err(err) => return Err(LoadFactoryError::JsonError(err)),
+    };
+
+    factory_json.remap(old_name, new_name);
+
+    write_atomic(
+        path,
+        serde_json::to_string_pretty(&factory_json)
+            .expect("Failed to convert to json")
+            .as_bytes(),
+    )
+    .map_err(|SaveError::IoError(err)| LoadFactoryError::IoError(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::tests::plate_world;
+
+    #[test]
+    fn writes_a_line_per_recipe() {
+        let factory = Factory {
+            recipes: vec![(RecipeId(0), 1.5), (RecipeId(1), 2.)],
+        };
+
+        let mut lines = Vec::new();
+        factory.write_jsonl(&plate_world(), &mut lines).unwrap();
+        let lines = String::from_utf8(lines).unwrap();
 
-    for (recipe_name, rate) in factory_json.recipes {
-        let Some(recipe) = world.recipe_id_of_name(&recipe_name) else {
-            return Err(LoadFactoryError::BadRecipeName { recipe_name });
+        assert_eq!(lines.lines().count(), factory.recipes.len());
+        assert_eq!(
+            lines.lines().next().unwrap(),
+            r#"{"recipe":"Smelt","machines":1.5}"#
+        );
+    }
+
+    #[test]
+    fn writes_a_sankey_flow_per_contribution() {
+        let factory = Factory {
+            recipes: vec![(RecipeId(0), 1.), (RecipeId(1), 1.)],
         };
 
-        factory.recipes.push((recipe, rate));
+        assert_eq!(
+            factory.to_sankey(&plate_world()),
+            "Ore [30] Smelt\nSmelt [30] Ingot\nIngot [30] Press\nPress [20] Plate\n"
+        );
     }
 
-    Ok(factory)
+    #[test]
+    fn machine_lists_round_trip_through_the_importer() {
+        let world = plate_world();
+        let factory = Factory {
+            recipes: vec![(RecipeId(0), 2.), (RecipeId(1), 3.)],
+        };
+
+        // the inputs and outputs copied alongside the machines are skipped
+        let text = format!("{}\nInputs:\n  Ore 60 /min\n", factory.machine_list(&world));
+        let imported = Factory::from_text(&world, &text).unwrap();
+        assert_eq!(imported.recipes, factory.recipes);
+
+        let Err(errors) = Factory::from_text(&world, "2x Smelt\nthree Press\n1x Roll") else {
+            panic!("unknown lines were accepted");
+        };
+        assert!(matches!(errors[0], ParseFactoryError::BadLine { line: 2 }));
+        assert!(matches!(
+            &errors[1],
+            ParseFactoryError::BadRecipeName { line: 3, recipe_name } if recipe_name == "Roll"
+        ));
+    }
 }
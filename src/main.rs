@@ -1,9 +1,13 @@
+use config::{load_config, update_config, Theme, TimeBase, MAX_PRECISION};
 use eframe::egui;
 use pages::{DefaultPage, Page};
 
 pub mod builder;
+pub mod config;
 pub mod factory;
 pub mod pages;
+pub mod save;
+pub mod session;
 pub mod world;
 
 fn main() -> eframe::Result<()> {
@@ -12,32 +16,156 @@ fn main() -> eframe::Result<()> {
         .init()
         .unwrap();
 
+    let config = load_config();
+    let mut viewport = egui::ViewportBuilder::default();
+
+    if config.always_on_top {
+        viewport = viewport.with_always_on_top();
+    }
+
+    // reopen the window where it was last closed
+    if let Some(size) = config.window_size {
+        viewport = viewport.with_inner_size(size);
+    }
+
+    if let Some(position) = config.window_position {
+        viewport = viewport.with_position(position);
+    }
+
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().into(),
+        viewport,
         ..Default::default()
     };
 
     eframe::run_native(
         "Satisfactory Solver",
         options,
-        Box::new(|_| Box::new(SolverApp::new())),
+        Box::new(|cc| Box::new(SolverApp::new(&cc.egui_ctx))),
     )
 }
 
 struct SolverApp {
     page: Box<dyn Page>,
+    theme: Theme,
+    time_base: TimeBase,
+    precision: usize,
+    thousands_separator: bool,
+    always_on_top: bool,
 }
 
 impl SolverApp {
-    fn new() -> Self {
+    fn new(ctx: &egui::Context) -> Self {
+        let config = load_config();
+
+        ctx.set_visuals(visuals(config.theme));
+
         SolverApp {
             page: Box::new(pages::world::LandingPage::new()),
+            theme: config.theme,
+            time_base: config.time_base,
+            precision: config.precision.min(MAX_PRECISION),
+            thousands_separator: config.thousands_separator,
+            always_on_top: config.always_on_top,
         }
     }
 }
 
+fn visuals(theme: Theme) -> egui::Visuals {
+    match theme {
+        Theme::Dark => egui::Visuals::dark(),
+        Theme::Light => egui::Visuals::light(),
+    }
+}
+
 impl eframe::App for SolverApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // app wide settings, shown above every page
+        egui::TopBottomPanel::top("Settings").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let previous_theme = self.theme;
+
+                ui.selectable_value(&mut self.theme, Theme::Dark, "Dark");
+                ui.selectable_value(&mut self.theme, Theme::Light, "Light");
+
+                if self.theme != previous_theme {
+                    ctx.set_visuals(visuals(self.theme));
+
+                    let theme = self.theme;
+                    update_config(|config| config.theme = theme);
+                }
+
+                ui.separator();
+
+                let previous_time_base = self.time_base;
+
+                ui.selectable_value(&mut self.time_base, TimeBase::PerMinute, "Per Minute");
+                ui.selectable_value(&mut self.time_base, TimeBase::PerSecond, "Per Second");
+
+                if self.time_base != previous_time_base {
+                    let time_base = self.time_base;
+                    update_config(|config| config.time_base = time_base);
+                }
+
+                ui.separator();
+
+                ui.label("Decimals");
+
+                if ui
+                    .add(egui::DragValue::new(&mut self.precision).clamp_range(0..=MAX_PRECISION))
+                    .changed()
+                {
+                    let precision = self.precision;
+                    update_config(|config| config.precision = precision);
+                }
+
+                if ui
+                    .checkbox(&mut self.thousands_separator, "1,000")
+                    .on_hover_text("Separate thousands with commas")
+                    .changed()
+                {
+                    let thousands_separator = self.thousands_separator;
+                    update_config(|config| config.thousands_separator = thousands_separator);
+                }
+
+                ui.separator();
+
+                if ui
+                    .checkbox(&mut self.always_on_top, "Always on top")
+                    .changed()
+                {
+                    let level = if self.always_on_top {
+                        egui::WindowLevel::AlwaysOnTop
+                    } else {
+                        egui::WindowLevel::Normal
+                    };
+
+                    ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+
+                    let always_on_top = self.always_on_top;
+                    update_config(|config| config.always_on_top = always_on_top);
+                }
+            });
+        });
+
+        if ctx.input(|input| input.viewport().close_requested()) {
+            let (inner_rect, outer_rect) =
+                ctx.input(|input| (input.viewport().inner_rect, input.viewport().outer_rect));
+
+            update_config(|config| {
+                if let Some(inner_rect) = inner_rect {
+                    config.window_size = Some(inner_rect.size().into());
+                }
+
+                if let Some(outer_rect) = outer_rect {
+                    config.window_position = Some(outer_rect.min.into());
+                }
+            });
+        }
+
+        pages::set_time_base(ctx, self.time_base);
+        pages::set_precision(ctx, self.precision);
+        pages::set_thousands_separator(ctx, self.thousands_separator);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             self.page = std::mem::replace(&mut self.page, Box::new(DefaultPage)).show(ui);
         });
@@ -1,41 +1,1016 @@
-use eframe::egui::{ComboBox, RichText, ScrollArea, Ui, Vec2};
-use log::debug;
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{Duration, Instant, SystemTime},
+};
+
+use eframe::egui::{
+    Align, Button, CollapsingHeader, Color32, ComboBox, DragValue, Grid, Key, RichText, ScrollArea,
+    Slider, Ui, Window,
+};
+use egui_plot::{Bar, BarChart, Plot};
 
 use crate::{
-    builder::{load_rule_list, save_rule_list, Constraint, Problem, Rule, RuleList},
+    builder::{
+        compare_objectives, load_rule_list, remap_rule_list, save_rule_list, solve_all, Basket,
+        Constraint, LoadRuleListError, ObjectiveMetrics, Problem, Rule, RuleList, SolveResult,
+        VariableRef, MAX_SOFT_RULE_PRIORITY,
+    },
+    config::TimeBase,
     factory::{Factory, NetResources},
+    save::{modified_time, SaveError},
+    session::{load_session, reload_session, save_session, LoadSessionError, Session},
     world::{RecipeId, ResourceId, VariableId, World},
 };
 
-use super::{factory::EditFactoryPage, Page};
+use super::{
+    columns, copy_factory_text, export_sankey, factory::EditFactoryPage, factory_summary,
+    format_number, format_rate, json_error_message, net_rate_color, parse_number, rate_color,
+    rate_unit, recipe_checkboxes, recipe_options, recipe_tooltip, resource_options,
+    resource_swatch, save_feedback, search_field, shift_shortcut_pressed, shortcut_pressed,
+    time_base, with_separators, world_error_message, world_reload_row, Page, DEFAULT_COLUMN_SPLITS,
+};
 
 pub struct BuildFactoryPage {
     world: World,
+    /// the path of the file the world was loaded from, saved in sessions
+    world_path: String,
+    /// when the world file was last modified as of loading it, to notice edits made on disk
+    world_modified: Option<SystemTime>,
+    /// the result of the last world reload
+    world_feedback: String,
+
+    session_path: String,
+    /// the result of the last session save or load
+    session_feedback: String,
+
+    /// the product and rate of the quick target form
+    target_resource: Option<ResourceId>,
+    target_rate: String,
+
     path_field: String,
     /// each element is a rule list, a uniqe ui id and a rule builder if building a rule
     rule_lists: Vec<(u64, RuleList, Option<RuleBuilder>)>,
+    /// which rules are selected for bulk actions, keyed by the rule list's ui id and in the same order as its rules
+    selected_rules: HashMap<u64, Vec<bool>>,
+    /// whether the rule list at `path_field` was saved, or why it couldn't be loaded
+    rule_list_feedback: String,
+    /// a name the rule list at `path_field` refers to that isn't in the world, and the name to map it to
+    remap: Option<(VariableRef, Option<String>)>,
+    /// a blend being set up, added as a new rule list when done
+    blend_builder: Option<BlendBuilder>,
+    basket_builder: Option<BasketBuilder>,
     rule_list_id_incrementor: u64,
 
-    optimizations: Vec<(u64, Option<VariableId>, String)>,
+    /// optimizations belong to the page rather than to a rule list, since a problem has one objective.
+    /// saving a rule list writes the current optimizations into the file,
+    /// and loading a file that has optimizations replaces them
+    ///
+    /// each element is a ui id, a variable, its weight and its priority tier.
+    /// with more than one tier, lower tiers are optimized first and kept at their best
+    /// while optimizing higher tiers
+    optimizations: Vec<(u64, Option<VariableId>, f64, u32)>,
     optimization_id_incrementor: u64,
-
-    solution: Result<(Factory, NetResources), String>,
+    /// the preset the optimization entries were filled from, custom once they're edited
+    preset: ObjectivePreset,
+
+    /// minimize raw resources when there are no optimizations
+    minimize_raw: bool,
+    /// maximize the sink points of surplus resources alongside the optimizations
+    sink_points: bool,
+    /// maximize the smallest of the optimization variables instead of their weighted sum
+    balance_outputs: bool,
+    /// recipes using fewer machines than this are pruned from the solution, zero disables pruning
+    prune_threshold: f64,
+    /// keep recipes the solver only touched by a rounding error
+    show_marginal: bool,
+    /// reject solutions where rounding leaves a resource short
+    strict_balance: bool,
+
+    /// each element is a ui id, a recipe and the most machines it may use
+    recipe_caps: Vec<(u64, Option<RecipeId>, String)>,
+    recipe_cap_id_incrementor: u64,
+
+    /// each element is a ui id, a resource and the most that can be brought in from outside
+    imports: Vec<(u64, Option<ResourceId>, String)>,
+    import_id_incrementor: u64,
+
+    /// each element is a ui id, two variables and the factor keeping the first a multiple of the second
+    ratios: Vec<(u64, Option<VariableId>, Option<VariableId>, String)>,
+    ratio_id_incrementor: u64,
+
+    /// each element is a ui id, two variables and the percentage of the second the first may be at most
+    percentages: Vec<(u64, Option<VariableId>, Option<VariableId>, String)>,
+    percentage_id_incrementor: u64,
+
+    /// each element is a ui id, a tag, and a constraint with its value on the total machines of recipes with the tag
+    tag_rules: Vec<(u64, Option<String>, Constraint, String)>,
+    tag_rule_id_incrementor: u64,
+    /// the distinct tags of the world's recipes
+    tags: Vec<String>,
+
+    /// the world's recipes grouped by building, for the recipe checkboxes
+    buildings: Vec<(String, Vec<RecipeId>)>,
+    /// whether each recipe may be used, unchecked recipes are disabled in the problem
+    allowed_recipes: Vec<bool>,
+    /// whether each recipe must run, checked recipes are required in the problem
+    required_recipes: Vec<bool>,
+    /// the allowed recipes changed since the last solve
+    recipes_changed: bool,
+    /// recipes kept at the machine count they had when pinned, so re-solving doesn't move them
+    ///
+    /// each pin is an equal rule, so pins that conflict with newer rules make the problem infeasible
+    pinned: Vec<(RecipeId, f64)>,
+
+    solution: Solution,
+    /// the factory solved before the current solution, to show what changed
+    previous_factory: Option<Factory>,
+    /// recently solved problems keyed by [Problem::cache_key], most recent first
+    solve_cache: VecDeque<(u64, Solution)>,
+    /// a solve running on a background thread, with its cache key
+    ///
+    /// the thread owns a copy of the world and sends the solved factory back through the channel,
+    /// which is polled each frame. dropping the receiver cancels the solve by discarding its result
+    pending: Option<(u64, Receiver<(SolveResult, Duration)>)>,
+    /// how long the worker took to solve the current solution, none if it wasn't solved by the worker
+    solve_time: Option<Duration>,
+    /// the problem most recently sent to the solver
+    last_problem: Option<Problem>,
+    /// the tiers and prune threshold `last_problem` was solved with, so it can be solved again the same way
+    last_tiers: Vec<Vec<(VariableId, f64)>>,
+    last_prune_threshold: f64,
+    /// the variables the current solution was optimized for, shown above the output
+    optimized: Vec<VariableId>,
+    /// the dump of the linear program shown in the "LP" window, if it's open
+    lp_description: Option<String>,
+    /// the result of the last feasibility check, cleared by solving
+    feasibility: Option<String>,
+    /// why each explained recipe is in the current solution, cleared when the solution changes
+    explanations: Vec<(RecipeId, String)>,
+    /// a recipe being explained on a background thread, which sends back the loss without it
+    explaining: Option<(RecipeId, Receiver<Loss>)>,
+
+    /// a recipe forced to a machine count to see how the rest of the factory changes
+    what_if_recipe: Option<RecipeId>,
+    what_if_machines: f64,
+    /// the last problem solved again with the forced recipe, cleared when the solution changes
+    what_if_solution: Option<Solution>,
+
+    /// the results of solving each rule list on its own, keyed by the rule list's ui id
+    batch_solutions: Vec<(u64, Result<Factory, String>)>,
+    /// the metrics of solving the rules under each objective preset, from "Compare Objectives"
+    comparison: Vec<(String, ObjectiveMetrics)>,
+
+    /// a resource clicked in the net rate chart, scrolled to in the net resources next frame
+    scroll_to_resource: Option<ResourceId>,
+    /// the resource or recipe hovered in the output last frame, highlighted along with what it touches
+    hovered: Option<VariableId>,
+    /// where the rules and optimization columns end, as fractions of the width
+    column_splits: [f32; 2],
+    /// "Clear All" was clicked and is waiting to be confirmed
+    confirm_clear: bool,
 }
 
+/// a solved factory with its objective value or a message explaining why there is none
+type Solution = Result<(Factory, NetResources, f64), String>;
+
+/// how much the objective drops without a recipe, see [Problem::loss_without]
+type Loss = Result<Option<f64>, String>;
+
+/// how many solved problems to remember
+const SOLVE_CACHE_SIZE: usize = 16;
+
+/// the least number of machines the what if slider goes up to, so recipes not in the solution can be tried
+const WHAT_IF_MIN_RANGE: f64 = 10.;
+
 impl BuildFactoryPage {
-    pub fn new(world: World) -> Self {
+    pub fn new(world: World, world_path: String) -> Self {
+        let tags = world.tags();
+        let buildings = world.recipes_by_building();
+        let allowed_recipes = vec![true; world.recipes.len()];
+        let required_recipes = vec![false; world.recipes.len()];
+
         BuildFactoryPage {
             world,
+            world_modified: modified_time(&world_path),
+            world_path,
+            world_feedback: String::new(),
+
+            session_path: String::new(),
+            session_feedback: String::new(),
+
+            target_resource: None,
+            target_rate: "0".into(),
+
             path_field: String::new(),
             rule_lists: Vec::new(),
+            selected_rules: HashMap::new(),
+            rule_list_feedback: String::new(),
+            remap: None,
+            blend_builder: None,
+            basket_builder: None,
             rule_list_id_incrementor: 0,
 
             optimizations: Vec::new(),
             optimization_id_incrementor: 0,
+            preset: ObjectivePreset::MinimizeRaw,
+            minimize_raw: true,
+            sink_points: false,
+            balance_outputs: false,
+            prune_threshold: 0.,
+            show_marginal: false,
+            strict_balance: false,
+
+            recipe_caps: Vec::new(),
+            recipe_cap_id_incrementor: 0,
+
+            ratios: Vec::new(),
+            ratio_id_incrementor: 0,
+
+            percentages: Vec::new(),
+            percentage_id_incrementor: 0,
+
+            imports: Vec::new(),
+            import_id_incrementor: 0,
+
+            tag_rules: Vec::new(),
+            tag_rule_id_incrementor: 0,
+            tags,
+
+            buildings,
+            allowed_recipes,
+            required_recipes,
+            recipes_changed: false,
+            pinned: Vec::new(),
 
             solution: Err("".into()),
+            previous_factory: None,
+            solve_cache: VecDeque::new(),
+            pending: None,
+            solve_time: None,
+            last_problem: None,
+            last_tiers: Vec::new(),
+            last_prune_threshold: 0.,
+            optimized: Vec::new(),
+            lp_description: None,
+            feasibility: None,
+            explanations: Vec::new(),
+            explaining: None,
+
+            what_if_recipe: None,
+            what_if_machines: 0.,
+            what_if_solution: None,
+
+            batch_solutions: Vec::new(),
+            comparison: Vec::new(),
+
+            scroll_to_resource: None,
+            hovered: None,
+            column_splits: DEFAULT_COLUMN_SPLITS,
+            confirm_clear: false,
         }
     }
+
+    /// restores the page from a saved session
+    fn from_session(session: Session) -> Self {
+        let mut page = BuildFactoryPage::new(session.world, session.world_path);
+
+        for rule_list in session.rule_lists {
+            page.rule_lists
+                .push((page.rule_list_id_incrementor, rule_list, None));
+            page.rule_list_id_incrementor += 1;
+        }
+
+        for (variable, weight, tier) in session.optimizations {
+            if !page.optimized.contains(&variable) {
+                page.optimized.push(variable);
+            }
+
+            page.optimizations.push((
+                page.optimization_id_incrementor,
+                Some(variable),
+                weight,
+                tier,
+            ));
+            page.optimization_id_incrementor += 1;
+        }
+
+        for RecipeId(index) in session.disabled {
+            page.allowed_recipes[index] = false;
+        }
+
+        for RecipeId(index) in session.required {
+            page.required_recipes[index] = true;
+        }
+
+        for (recipe, max) in session.recipe_caps {
+            page.recipe_caps
+                .push((page.recipe_cap_id_incrementor, Some(recipe), max));
+            page.recipe_cap_id_incrementor += 1;
+        }
+
+        for (resource, cap) in session.imports {
+            page.imports
+                .push((page.import_id_incrementor, Some(resource), cap));
+            page.import_id_incrementor += 1;
+        }
+
+        for (a, b, factor) in session.ratios {
+            page.ratios
+                .push((page.ratio_id_incrementor, Some(a), Some(b), factor));
+            page.ratio_id_incrementor += 1;
+        }
+
+        for (a, b, percentage) in session.percentages {
+            page.percentages
+                .push((page.percentage_id_incrementor, Some(a), Some(b), percentage));
+            page.percentage_id_incrementor += 1;
+        }
+
+        for (tag, constraint, value) in session.tag_rules {
+            page.tag_rules
+                .push((page.tag_rule_id_incrementor, Some(tag), constraint, value));
+            page.tag_rule_id_incrementor += 1;
+        }
+
+        page.pinned = session.pinned;
+        page.minimize_raw = session.minimize_raw;
+        page.sink_points = session.sink_points;
+        page.balance_outputs = session.balance_outputs;
+        page.prune_threshold = session.prune_threshold;
+        page.show_marginal = session.show_marginal;
+        page.strict_balance = session.strict_balance;
+
+        if let Some(factory) = session.factory {
+            let net_resources = factory.net_resources(&page.world);
+            let objective = page.objective_of(&factory, &net_resources);
+
+            page.solution = Ok((factory, net_resources, objective));
+        }
+
+        page
+    }
+
+    /// the current state of the page as a session
+    fn session(&self) -> Session {
+        Session {
+            world_path: self.world_path.clone(),
+            world: self.world.clone(),
+            rule_lists: self
+                .rule_lists
+                .iter()
+                .map(|(_, rule_list, _)| RuleList {
+                    optimizations: Vec::new(),
+                    ..rule_list.clone()
+                })
+                .collect(),
+            optimizations: self
+                .optimizations
+                .iter()
+                .filter_map(|(_, variable, weight, tier)| Some(((*variable)?, *weight, *tier)))
+                .collect(),
+            factory: self
+                .solution
+                .as_ref()
+                .ok()
+                .map(|(factory, _, _)| factory.clone()),
+            disabled: (0..self.world.recipes.len())
+                .filter(|&index| !self.allowed_recipes[index])
+                .map(RecipeId)
+                .collect(),
+            required: (0..self.world.recipes.len())
+                .filter(|&index| self.required_recipes[index])
+                .map(RecipeId)
+                .collect(),
+            recipe_caps: self
+                .recipe_caps
+                .iter()
+                .filter_map(|(_, recipe, max)| Some(((*recipe)?, max.clone())))
+                .collect(),
+            imports: self
+                .imports
+                .iter()
+                .filter_map(|(_, resource, cap)| Some(((*resource)?, cap.clone())))
+                .collect(),
+            ratios: self
+                .ratios
+                .iter()
+                .filter_map(|(_, a, b, factor)| Some(((*a)?, (*b)?, factor.clone())))
+                .collect(),
+            percentages: self
+                .percentages
+                .iter()
+                .filter_map(|(_, a, b, percentage)| Some(((*a)?, (*b)?, percentage.clone())))
+                .collect(),
+            tag_rules: self
+                .tag_rules
+                .iter()
+                .filter_map(|(_, tag, constraint, value)| {
+                    Some((tag.clone()?, *constraint, value.clone()))
+                })
+                .collect(),
+            pinned: self.pinned.clone(),
+            minimize_raw: self.minimize_raw,
+            sink_points: self.sink_points,
+            balance_outputs: self.balance_outputs,
+            prune_threshold: self.prune_threshold,
+            show_marginal: self.show_marginal,
+            strict_balance: self.strict_balance,
+        }
+    }
+
+    /// the objective the page's settings would have solved a factory for, the sum of the optimizations
+    /// or their smallest value when balancing outputs, plus the points of sunk resources
+    fn objective_of(&self, factory: &Factory, net_resources: &NetResources) -> f64 {
+        let values = self
+            .valid_optimizations()
+            .into_iter()
+            .map(|(variable, weight)| (weight, factory.value_of(net_resources, variable)));
+
+        let mut objective = if self.balance_outputs {
+            values
+                .map(|(_, value)| value)
+                .reduce(f64::min)
+                .unwrap_or(0.)
+        } else {
+            values.map(|(weight, value)| weight * value).sum()
+        };
+
+        if self.sink_points {
+            objective += self
+                .world
+                .resources
+                .iter()
+                .zip(net_resources.resources.iter())
+                .map(|(resource, (rate, _))| resource.points.unwrap_or(0.) * rate)
+                .sum::<f64>();
+        }
+
+        objective
+    }
+
+    /// replaces the optimization entries
+    fn set_optimizations(&mut self, optimizations: Vec<(VariableId, f64)>) {
+        self.optimizations.clear();
+
+        for (variable, weight) in optimizations {
+            self.optimizations
+                .push((self.optimization_id_incrementor, Some(variable), weight, 1));
+            self.optimization_id_incrementor += 1;
+        }
+    }
+
+    /// the optimizations a preset fills in, none for custom or when the preset doesn't apply to the world
+    fn preset_optimizations(&self, preset: ObjectivePreset) -> Option<Vec<(VariableId, f64)>> {
+        match preset {
+            ObjectivePreset::Custom => None,
+            // an empty objective falls back to the default raw resource minimization
+            ObjectivePreset::MinimizeRaw => Some(Vec::new()),
+            // maximizing the net of power minimizes how much is consumed
+            ObjectivePreset::MinimizePower => Some(vec![(self.world.power?.variable_id(), 1.)]),
+            ObjectivePreset::MinimizeMachines => Some(
+                (0..self.world.recipes.len())
+                    .map(|index| (RecipeId(index).variable_id(), -1.))
+                    .collect(),
+            ),
+            ObjectivePreset::MaximizeOutput => {
+                Some(vec![(self.target_resource?.variable_id(), 1.)])
+            }
+        }
+    }
+
+    /// the optimization entries that have a variable and a valid weight
+    fn valid_optimizations(&self) -> Vec<(VariableId, f64)> {
+        self.optimizations
+            .iter()
+            .filter_map(|(_, variable, weight, _)| Some(((*variable)?, *weight)))
+            .collect()
+    }
+
+    /// saves a rule list along with the current optimizations
+    fn save_rule_list(&self, rule_list: &RuleList) -> Result<(), SaveError> {
+        let rule_list = RuleList {
+            optimizations: self.valid_optimizations(),
+            ..rule_list.clone()
+        };
+
+        save_rule_list(&self.world, &rule_list, &self.path_field)
+    }
+
+    /// adds the disallowed and required recipes, pins, recipe caps, ratios, percentages, imports and tag rules to a problem
+    fn add_constraints(&self, problem: &mut Problem, time_base: TimeBase) -> Result<(), String> {
+        for (index, allowed) in self.allowed_recipes.iter().enumerate() {
+            if !allowed {
+                problem.disabled.push(RecipeId(index));
+            }
+        }
+
+        for (index, required) in self.required_recipes.iter().enumerate() {
+            if *required {
+                problem.required.push(RecipeId(index));
+            }
+        }
+
+        for &(recipe, machines) in self.pinned.iter() {
+            problem.rules.push(Rule {
+                variable: recipe.variable_id(),
+                constraint: Constraint::Equal(machines),
+                soft: false,
+                priority: 0,
+            });
+        }
+
+        for (_, recipe, max) in self.recipe_caps.iter() {
+            let Some(recipe) = recipe else {
+                continue;
+            };
+
+            let Some(max) = parse_number(max) else {
+                return Err(format!("Invalid number \"{}\" in recipe cap", max));
+            };
+
+            problem.rules.push(Rule {
+                variable: recipe.variable_id(),
+                constraint: Constraint::Less(max),
+                soft: false,
+                priority: 0,
+            });
+        }
+
+        for (_, a, b, factor) in self.ratios.iter() {
+            let (Some(a), Some(b)) = (a, b) else {
+                continue;
+            };
+
+            let Some(factor) = parse_number(factor) else {
+                return Err(format!("Invalid number \"{}\" in ratio", factor));
+            };
+
+            problem.ratios.push((*a, *b, factor));
+        }
+
+        for (_, a, b, percentage) in self.percentages.iter() {
+            let (Some(a), Some(b)) = (a, b) else {
+                continue;
+            };
+
+            let Some(percentage) = parse_number(percentage) else {
+                return Err(format!("Invalid number \"{}\" in percentage", percentage));
+            };
+
+            problem.fractions.push((*a, *b, percentage / 100.));
+        }
+
+        for (_, resource, cap) in self.imports.iter() {
+            let Some(resource) = resource else {
+                continue;
+            };
+
+            let Some(cap) = parse_number(cap) else {
+                return Err(format!("Invalid number \"{}\" in import", cap));
+            };
+
+            problem
+                .imports
+                .push((*resource, time_base.from_display(cap)));
+        }
+
+        for (_, tag, constraint, value) in self.tag_rules.iter() {
+            let Some(tag) = tag else {
+                continue;
+            };
+
+            let Some(value) = parse_number(value) else {
+                return Err(format!("Invalid number \"{}\" in tag rule", value));
+            };
+
+            let constraint = match constraint {
+                Constraint::Less(_) => Constraint::Less(value),
+                Constraint::Equal(_) => Constraint::Equal(value),
+                Constraint::Greater(_) => Constraint::Greater(value),
+                Constraint::Unconstrained => continue,
+            };
+
+            problem.tag_rules.push((tag.clone(), constraint));
+        }
+
+        Ok(())
+    }
+
+    /// empties the rule lists, optimizations and builder rules, allows every recipe again
+    /// and forgets the solution, keeping only the world
+    fn clear_all(&mut self) {
+        self.rule_lists.clear();
+        self.selected_rules.clear();
+        self.blend_builder = None;
+        self.basket_builder = None;
+        self.optimizations.clear();
+        self.pinned.clear();
+
+        self.recipe_caps.clear();
+        self.ratios.clear();
+        self.percentages.clear();
+        self.imports.clear();
+        self.tag_rules.clear();
+        self.allowed_recipes.fill(true);
+        self.required_recipes.fill(false);
+        self.recipes_changed = false;
+
+        self.solution = Err("".into());
+        self.previous_factory = None;
+        self.pending = None;
+        self.solve_time = None;
+        self.last_problem = None;
+        self.optimized.clear();
+        self.lp_description = None;
+        self.feasibility = None;
+        self.explanations.clear();
+        self.explaining = None;
+        self.what_if_solution = None;
+        self.batch_solutions.clear();
+        self.comparison.clear();
+    }
+
+    /// replaces the solution, remembering the previous factory
+    fn set_solution(&mut self, solution: Solution) {
+        let previous = std::mem::replace(&mut self.solution, solution);
+        self.explanations.clear();
+        self.explaining = None;
+        self.what_if_solution = None;
+
+        if let Ok((factory, _, _)) = previous {
+            self.previous_factory = Some(factory);
+        }
+    }
+
+    /// a recipe slider re-solving the last problem with the recipe forced to the slider's machine count,
+    /// returns the what if solution if the user chose to use it
+    fn what_if(&mut self, ui: &mut Ui) -> Option<Solution> {
+        let (Ok((factory, net_resources, _)), Some(problem)) = (&self.solution, &self.last_problem)
+        else {
+            return None;
+        };
+
+        let mut use_solution = false;
+
+        ui.collapsing("What If", |ui| {
+            let current = |recipe| {
+                factory
+                    .recipes
+                    .iter()
+                    .find(|&&(other, _)| other == recipe)
+                    .map_or(0., |&(_, rate)| rate)
+            };
+
+            let mut resolve = false;
+
+            ui.horizontal(|ui| {
+                let selected_text = match self.what_if_recipe {
+                    Some(recipe) => self.world.name_of_recipe(recipe),
+                    None => "...",
+                };
+
+                let previous_recipe = self.what_if_recipe;
+
+                ComboBox::from_id_source("What If Recipe")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        recipe_options(ui, &self.world, &mut self.what_if_recipe);
+                    });
+
+                let Some(recipe) = self.what_if_recipe else {
+                    return;
+                };
+
+                // start from the recipe's count in the solution
+                if self.what_if_recipe != previous_recipe {
+                    self.what_if_machines = current(recipe);
+                }
+
+                let max = (current(recipe) * 2.).max(WHAT_IF_MIN_RANGE);
+
+                let response =
+                    ui.add(Slider::new(&mut self.what_if_machines, 0.0..=max).text("machines"));
+
+                // solving once the slider is let go keeps dragging from solving every frame
+                resolve = response.drag_stopped() || (response.changed() && !response.dragged());
+            });
+
+            if let (true, Some(recipe)) = (resolve, self.what_if_recipe) {
+                let mut problem = problem.clone();
+
+                problem.rules.push(Rule {
+                    variable: recipe.variable_id(),
+                    constraint: Constraint::Equal(self.what_if_machines),
+                    soft: false,
+                    priority: 0,
+                });
+
+                self.what_if_solution =
+                    Some(problem.solve(&self.world).map(|(factory, objective)| {
+                        let net_resources = factory.net_resources(&self.world);
+                        (factory, net_resources, objective)
+                    }));
+            }
+
+            match &self.what_if_solution {
+                Some(Ok((_, what_if_resources, _))) => {
+                    for (index, ((rate, _), (what_if_rate, _))) in net_resources
+                        .resources
+                        .iter()
+                        .zip(what_if_resources.resources.iter())
+                        .enumerate()
+                    {
+                        if (rate - what_if_rate).abs() < 1e-6 {
+                            continue;
+                        }
+
+                        ui.label(net_rate_color(
+                            format!(
+                                "{} {} -> {}",
+                                self.world.name_of_resource(ResourceId(index)),
+                                format_rate(ui, &self.world, ResourceId(index), *rate),
+                                format_rate(ui, &self.world, ResourceId(index), *what_if_rate)
+                            ),
+                            what_if_rate - rate,
+                        ));
+                    }
+
+                    use_solution = ui.button("Use").clicked();
+                }
+                Some(Err(response)) => {
+                    ui.label(response);
+                }
+                None => (),
+            }
+        });
+
+        if use_solution {
+            self.what_if_solution.take()
+        } else {
+            None
+        }
+    }
+
+    /// solves every rule list separately in place of the rules of a problem built from the page
+    fn solve_each(&mut self, problem: &Problem) {
+        let lists: Vec<RuleList> = self
+            .rule_lists
+            .iter()
+            .map(|(_, rule_list, _)| rule_list.clone())
+            .collect();
+
+        self.batch_solutions = self
+            .rule_lists
+            .iter()
+            .map(|(ui_id, _, _)| *ui_id)
+            .zip(solve_all(&self.world, problem, &lists))
+            .collect();
+    }
+
+    /// a section per rule list solved by "Solve All"
+    fn batch_solutions(&mut self, ui: &mut Ui) {
+        let mut close = false;
+
+        ui.collapsing("Solve All", |ui| {
+            close = ui.button("Close").clicked();
+
+            for (index, (ui_id, solution)) in self.batch_solutions.iter().enumerate() {
+                ui.push_id(ui_id, |ui| {
+                    ui.collapsing(format!("Rule List {}", index + 1), |ui| match solution {
+                        Ok(factory) => {
+                            factory_summary(ui, &self.world, factory);
+
+                            for &(recipe, rate) in factory.recipes.iter() {
+                                ui.label(format!(
+                                    "{} {} machines",
+                                    self.world.name_of_recipe(recipe),
+                                    format_number(ui, rate)
+                                ));
+                            }
+                        }
+                        Err(response) => {
+                            ui.label(response);
+                        }
+                    });
+                });
+            }
+        });
+
+        if close {
+            self.batch_solutions.clear();
+        }
+    }
+
+    /// solves a problem under each objective preset that applies to the world
+    fn compare_objectives(&mut self, problem: &Problem) {
+        let objectives = [
+            ObjectivePreset::MinimizeRaw,
+            ObjectivePreset::MinimizePower,
+            ObjectivePreset::MinimizeMachines,
+        ]
+        .into_iter()
+        .filter_map(|preset| {
+            self.preset_optimizations(preset)
+                .map(|optimizations| (preset.name().to_string(), optimizations))
+        })
+        .collect();
+
+        self.comparison = compare_objectives(&self.world, problem, objectives);
+    }
+
+    /// a table of the metrics from "Compare Objectives"
+    fn comparison(&mut self, ui: &mut Ui) {
+        let mut close = false;
+
+        ui.collapsing("Compare Objectives", |ui| {
+            close = ui.button("Close").clicked();
+
+            Grid::new("Comparison").striped(true).show(ui, |ui| {
+                for heading in ["Objective", "Raw", "Power", "Machines"] {
+                    ui.label(RichText::new(heading).strong());
+                }
+                ui.end_row();
+
+                for (name, metrics) in self.comparison.iter() {
+                    ui.label(name);
+
+                    match metrics {
+                        Ok((raw, power, machines)) => {
+                            ui.label(format_number(ui, *raw));
+                            ui.label(match self.world.power {
+                                Some(resource) => format_rate(ui, &self.world, resource, *power),
+                                None => "-".into(),
+                            });
+                            ui.label(format_number(ui, *machines));
+                        }
+                        Err(response) => {
+                            ui.label(response);
+                        }
+                    }
+
+                    ui.end_row();
+                }
+            });
+        });
+
+        if close {
+            self.comparison.clear();
+        }
+    }
+
+    /// starts solving a problem on a background thread, reusing a cached solution if there is one
+    ///
+    /// with more than one tier of optimizations the tiers are solved lexicographically
+    /// and the objective is the sum of all of them, pruning marginal recipes after the last tier
+    fn solve(&mut self, mut problem: Problem, tiers: Vec<Vec<(VariableId, f64)>>) {
+        problem.keep_zero = self.show_marginal;
+        problem.strict_balance = self.strict_balance;
+        self.recipes_changed = false;
+        self.last_problem = Some(problem.clone());
+        self.last_tiers = tiers.clone();
+        self.last_prune_threshold = self.prune_threshold;
+
+        self.optimized.clear();
+        for variable in problem
+            .optimizations
+            .iter()
+            .map(|&(variable, _)| variable)
+            .chain(problem.balanced.iter().copied())
+        {
+            if !self.optimized.contains(&variable) {
+                self.optimized.push(variable);
+            }
+        }
+
+        let threshold = self.prune_threshold;
+
+        let key = {
+            let mut hasher = DefaultHasher::new();
+            problem.cache_key(&self.world).hash(&mut hasher);
+            threshold.to_bits().hash(&mut hasher);
+            tiers.len().hash(&mut hasher);
+            for tier in tiers.iter() {
+                tier.len().hash(&mut hasher);
+                for &(variable, coefficient) in tier.iter() {
+                    variable.hash(&mut hasher);
+                    coefficient.to_bits().hash(&mut hasher);
+                }
+            }
+            hasher.finish()
+        };
+
+        if let Some(index) = self
+            .solve_cache
+            .iter()
+            .position(|(cached, _)| *cached == key)
+        {
+            let entry = self.solve_cache.remove(index).unwrap();
+            self.set_solution(entry.1.clone());
+            self.solve_cache.push_front(entry);
+            self.pending = None;
+            self.solve_time = None;
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let world = self.world.clone();
+
+        std::thread::spawn(move || {
+            let start = Instant::now();
+
+            let result = solve_with_settings(&problem, &world, &tiers, threshold);
+
+            // the receiver is gone if the solve was cancelled
+            let _ = sender.send((result, start.elapsed()));
+        });
+
+        self.pending = Some((key, receiver));
+    }
+
+    /// starts working out on a background thread how much the current solution loses without a recipe,
+    /// solving again the way the current solution was solved
+    fn explain(&mut self, recipe: RecipeId) {
+        let (Ok((factory, _, _)), Some(problem)) = (&self.solution, &self.last_problem) else {
+            return;
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let world = self.world.clone();
+        let problem = problem.clone();
+        let factory = factory.clone();
+        let tiers = self.last_tiers.clone();
+        let threshold = self.last_prune_threshold;
+
+        std::thread::spawn(move || {
+            let loss = problem.loss_without(&world, &factory, recipe, |without| {
+                solve_with_settings(without, &world, &tiers, threshold)
+            });
+
+            // the receiver is gone if the solution changed in the meantime
+            let _ = sender.send(loss);
+        });
+
+        self.explaining = Some((recipe, receiver));
+    }
+
+    /// checks whether the background explanation has finished and stores it
+    fn poll_explaining(&mut self, ui: &Ui) {
+        let Some((recipe, receiver)) = &self.explaining else {
+            return;
+        };
+
+        let explanation = match receiver.try_recv() {
+            Ok(Ok(None)) => "Required, the rules can't be met without it".into(),
+            Ok(Ok(Some(loss))) => format!(
+                "Optimal, without it the objective drops by {}",
+                format_number(ui, loss)
+            ),
+            Ok(Err(response)) => response,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => "The solver stopped unexpectedly".into(),
+        };
+
+        let recipe = *recipe;
+        self.explaining = None;
+        self.explanations.retain(|&(other, _)| other != recipe);
+        self.explanations.push((recipe, explanation));
+    }
+
+    /// checks whether the background solve has finished and stores its solution
+    fn poll_pending(&mut self) {
+        let Some((key, receiver)) = &self.pending else {
+            return;
+        };
+
+        let (result, solve_time) = match receiver.try_recv() {
+            Ok((result, solve_time)) => (result, Some(solve_time)),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                (Err("The solver stopped unexpectedly".into()), None)
+            }
+        };
+
+        let key = *key;
+        self.pending = None;
+        self.solve_time = solve_time;
+
+        let solution = match result {
+            Err(response) if response.starts_with("Infeasible") && !self.recipe_caps.is_empty() => {
+                Err(format!("{}, the recipe caps may be too low", response))
+            }
+            Err(response) => Err(response),
+            Ok((factory, objective)) => {
+                let resources = factory.net_resources(&self.world);
+                Ok((factory, resources, objective))
+            }
+        };
+
+        self.solve_cache.push_front((key, solution.clone()));
+        self.set_solution(solution);
+        self.solve_cache.truncate(SOLVE_CACHE_SIZE);
+    }
 }
 
 impl Page for BuildFactoryPage {
@@ -44,20 +1019,189 @@ impl Page for BuildFactoryPage {
 
         let mut edit_factory = None;
 
-        let available_space = ui.available_rect_before_wrap();
-        let collumn_width = available_space.width() / 3.;
+        self.poll_pending();
+        self.poll_explaining(ui);
+
+        if self.pending.is_some() || self.explaining.is_some() {
+            // keep polling while the solver runs
+            ui.ctx().request_repaint();
+        }
+
+        let load_session_clicked = ui
+            .horizontal(|ui| {
+                ui.label("Session");
+                ui.text_edit_singleline(&mut self.session_path);
+
+                // ctrl+shift+s saves every rule list and setting, once the session has a path
+                if ui
+                    .button("Save Session")
+                    .on_hover_text("Ctrl+Shift+S")
+                    .clicked()
+                    || !self.session_path.is_empty() && shift_shortcut_pressed(ui, Key::S)
+                {
+                    self.session_feedback =
+                        save_feedback(save_session(&self.session(), &self.session_path));
+                }
+
+                let load = ui.button("Load Session").clicked();
+
+                ui.label(&self.session_feedback);
+
+                load
+            })
+            .inner;
+
+        let reload_world = world_reload_row(
+            ui,
+            &self.world_path,
+            self.world_modified,
+            &self.world_feedback,
+        );
+
+        // rules, optimizations, builder settings and the solution are carried over by name like a saved session
+        if reload_world {
+            match reload_session(self.session()) {
+                Ok((session, missing)) => {
+                    let mut page = BuildFactoryPage::from_session(session);
+                    page.session_path = self.session_path;
+                    page.column_splits = self.column_splits;
+                    page.preset = self.preset;
+
+                    page.world_feedback = if missing.is_empty() {
+                        "Reloaded the world".into()
+                    } else {
+                        format!(
+                            "Reloaded the world, dropped what referred to {}",
+                            missing.join(", ")
+                        )
+                    };
+                    return Box::new(page);
+                }
+                Err(err) => self.world_feedback = world_error_message(err),
+            }
+        }
+
+        ui.horizontal(|ui| {
+            if self.confirm_clear {
+                ui.label(
+                    "Clear every rule list, optimization, cap, ratio, percentage, import, tag rule, \
+                    recipe choice and the solution?",
+                );
+
+                if ui.button("Clear").clicked() {
+                    self.clear_all();
+                    self.confirm_clear = false;
+                }
+
+                if ui.button("Cancel").clicked() {
+                    self.confirm_clear = false;
+                }
+            } else if ui
+                .button("Clear All")
+                .on_hover_text(
+                    "Remove every rule list, optimization and builder rule and allow every recipe, \
+                    the world is kept",
+                )
+                .clicked()
+            {
+                self.confirm_clear = true;
+            }
+        });
+
+        if load_session_clicked {
+            match load_session(&self.session_path) {
+                Ok(session) => {
+                    let mut page = BuildFactoryPage::from_session(session);
+                    page.session_path = self.session_path;
+                    page.session_feedback = "Loaded session".into();
+                    return Box::new(page);
+                }
+                Err(err) => {
+                    self.session_feedback = match err {
+                        LoadSessionError::IoError(_) => "Io Error".into(),
+                        LoadSessionError::JsonError(err) => json_error_message(&err),
+                        LoadSessionError::MissingWorld { world_path } => {
+                            format!("The world file \"{}\" is missing", world_path)
+                        }
+                        LoadSessionError::WorldError(err) => {
+                            format!("Failed to load the world: {:?}", err)
+                        }
+                        LoadSessionError::BadRecipeName { recipe_name } => {
+                            format!("The world has no recipe \"{}\"", recipe_name)
+                        }
+                        LoadSessionError::RuleListError(err) => {
+                            format!("Bad rule list: {:?}", err)
+                        }
+                        LoadSessionError::FactoryError(err) => {
+                            format!("Bad factory: {:?}", err)
+                        }
+                    };
+                }
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Target product");
+
+            let selected_text = match self.target_resource {
+                Some(resource) => self.world.name_of_resource(resource),
+                None => "...",
+            };
+
+            ComboBox::from_id_source("Target Product")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for (resource_id, resource) in self.world.resources.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut self.target_resource,
+                            Some(ResourceId(resource_id)),
+                            &resource.name,
+                        );
+                    }
+                });
+
+            ui.text_edit_singleline(&mut self.target_rate);
+            let time_base = time_base(ui);
+            ui.label(rate_unit(ui, &self.world, self.target_resource));
+
+            let rate = parse_number(&self.target_rate).map(|rate| time_base.from_display(rate));
+
+            if rate.is_none() {
+                ui.label("Invalid number");
+            }
+
+            if ui.button("Solve").clicked() {
+                if let (Some(resource), Some(rate)) = (self.target_resource, rate) {
+                    let mut problem = Problem::for_output(&self.world, resource, rate);
+
+                    match self.add_constraints(&mut problem, time_base) {
+                        Ok(()) => self.solve(problem, Vec::new()),
+                        Err(response) => self.solution = Err(response),
+                    }
+                }
+            }
+        });
+
+        let [rules_collumn, optimization_collumn, output_collumn] =
+            columns(ui, &mut self.column_splits);
 
         ui.push_id("Rules", |ui| {
-            let mut collumn = available_space;
-            collumn.set_width(collumn_width);
-            ui.allocate_ui_at_rect(collumn, |ui| {
+            ui.allocate_ui_at_rect(rules_collumn, |ui| {
                 ui.heading("Rules");
 
-                let (new_list, load_list) = ui
+                let (new_list, mut load_list, new_blend, new_basket) = ui
                     .horizontal(|ui| {
                         (
                             ui.button("New List").clicked(),
                             ui.button("Load List").clicked(),
+                            ui.button("New Blend")
+                                .on_hover_text(
+                                    "Produce a resource from a mix of recipes with minimums",
+                                )
+                                .clicked(),
+                            ui.button("New Basket")
+                                .on_hover_text("Produce several resources in set proportions")
+                                .clicked(),
                         )
                     })
                     .inner;
@@ -73,23 +1217,208 @@ impl Page for BuildFactoryPage {
                     self.rule_list_id_incrementor += 1;
                 }
 
+                if new_blend {
+                    self.blend_builder = Some(BlendBuilder::new());
+                }
+
+                if let Some(blend_builder) = self.blend_builder.as_mut() {
+                    let mut close = false;
+
+                    ui.push_id("Blend", |ui| {
+                        blend_builder.show(&self.world, ui);
+
+                        let (cancel, add) = ui
+                            .horizontal(|ui| {
+                                (ui.button("Cancel").clicked(), ui.button("Add").clicked())
+                            })
+                            .inner;
+
+                        if cancel {
+                            close = true;
+                        }
+
+                        if add {
+                            if let Some(rules) = blend_builder.build(&self.world, time_base(ui)) {
+                                self.rule_lists.push((
+                                    self.rule_list_id_incrementor,
+                                    RuleList {
+                                        rules,
+                                        ..Default::default()
+                                    },
+                                    None,
+                                ));
+                                self.rule_list_id_incrementor += 1;
+
+                                close = true;
+                            }
+                        }
+                    });
+
+                    if close {
+                        self.blend_builder = None;
+                    }
+
+                    ui.separator();
+                }
+
+                if new_basket {
+                    self.basket_builder = Some(BasketBuilder::new());
+                }
+
+                if let Some(basket_builder) = self.basket_builder.as_mut() {
+                    let mut close = false;
+
+                    ui.push_id("Basket", |ui| {
+                        basket_builder.show(&self.world, ui);
+
+                        let (cancel, add) = ui
+                            .horizontal(|ui| {
+                                (ui.button("Cancel").clicked(), ui.button("Add").clicked())
+                            })
+                            .inner;
+
+                        if cancel {
+                            close = true;
+                        }
+
+                        if add {
+                            if let Some(basket) = basket_builder.build(time_base(ui)) {
+                                self.rule_lists.push((
+                                    self.rule_list_id_incrementor,
+                                    RuleList {
+                                        baskets: vec![basket],
+                                        ..Default::default()
+                                    },
+                                    None,
+                                ));
+                                self.rule_list_id_incrementor += 1;
+
+                                close = true;
+                            }
+                        }
+                    });
+
+                    if close {
+                        self.basket_builder = None;
+                    }
+
+                    ui.separator();
+                }
+
+                // ctrl+s saves the top rule list
+                if shortcut_pressed(ui, Key::S) {
+                    if let Some((_, rule_list, _)) = self.rule_lists.first() {
+                        self.rule_list_feedback = save_feedback(self.save_rule_list(rule_list));
+                    }
+                }
+
+                if let Some((missing, replacement)) = &mut self.remap {
+                    let remap = ui
+                        .horizontal(|ui| {
+                            let (kind, missing_name, names): (_, _, Vec<&str>) = match missing {
+                                VariableRef::Resource(name) => (
+                                    "resource",
+                                    name,
+                                    self.world
+                                        .resources
+                                        .iter()
+                                        .map(|r| r.name.as_str())
+                                        .collect(),
+                                ),
+                                VariableRef::Recipe(name) => (
+                                    "recipe",
+                                    name,
+                                    self.world.recipes.iter().map(|r| r.name.as_str()).collect(),
+                                ),
+                            };
+
+                            ui.label(format!("Map {} \"{}\" to", kind, missing_name));
+
+                            ComboBox::from_id_source("Remap")
+                                .selected_text(replacement.as_deref().unwrap_or_default())
+                                .show_ui(ui, |ui| {
+                                    let matches = search_field(ui);
+
+                                    for name in names {
+                                        if matches(name) {
+                                            ui.selectable_value(
+                                                replacement,
+                                                Some(name.to_string()),
+                                                name,
+                                            );
+                                        }
+                                    }
+                                });
+
+                            ui.add_enabled(replacement.is_some(), Button::new("Remap"))
+                                .on_hover_text("Rewrites the rule list file with the new name")
+                                .clicked()
+                        })
+                        .inner;
+
+                    if remap {
+                        if let Some(replacement) = replacement {
+                            match remap_rule_list(&self.path_field, missing, replacement) {
+                                Ok(()) => load_list = true,
+                                Err(_) => {
+                                    self.rule_list_feedback =
+                                        "Failed to rewrite the rule list".into()
+                                }
+                            }
+                        }
+                    }
+                }
+
                 if load_list {
-                    if let Ok(rule_list) = load_rule_list(&self.world, &self.path_field) {
-                        self.rule_lists
-                            .push((self.rule_list_id_incrementor, rule_list, None));
-                        self.rule_list_id_incrementor += 1;
+                    self.rule_list_feedback.clear();
+                    self.remap = None;
+
+                    match load_rule_list(&self.world, &self.path_field) {
+                        Ok(mut rule_list) => {
+                            let optimizations = std::mem::take(&mut rule_list.optimizations);
+
+                            if !optimizations.is_empty() {
+                                self.set_optimizations(optimizations);
+                            }
+
+                            self.rule_lists
+                                .push((self.rule_list_id_incrementor, rule_list, None));
+                            self.rule_list_id_incrementor += 1;
+                        }
+                        Err(err) => {
+                            self.rule_list_feedback = match err {
+                                LoadRuleListError::IoError(_) => "Io Error".into(),
+                                LoadRuleListError::JsonError(err) => json_error_message(&err),
+                                LoadRuleListError::BadRecipeName { recipe_name } => {
+                                    let feedback = format!("Bad recipe name \"{}\"", recipe_name);
+                                    self.remap = Some((VariableRef::Recipe(recipe_name), None));
+                                    feedback
+                                }
+                                LoadRuleListError::BadResourceName { resource_name } => {
+                                    let feedback =
+                                        format!("Bad resource name \"{}\"", resource_name);
+                                    self.remap = Some((VariableRef::Resource(resource_name), None));
+                                    feedback
+                                }
+                            };
+                        }
                     }
                 }
 
+                if !self.rule_list_feedback.is_empty() {
+                    ui.label(&self.rule_list_feedback);
+                }
+
                 ScrollArea::new([false, true]).show(ui, |ui| {
                     let mut up = None;
                     let mut down = None;
                     let mut delete = None;
+                    let mut save = None;
 
                     for (index, (ui_id, rule_list, rule_builder)) in
                         self.rule_lists.iter_mut().enumerate()
                     {
-                        ui.push_id(ui_id, |ui| {
+                        ui.push_id(*ui_id, |ui| {
                             ui.collapsing("Rule List", |ui| {
                                 let (
                                     up_clicked,
@@ -103,7 +1432,15 @@ impl Page for BuildFactoryPage {
                                             ui.button("Up").clicked(),
                                             ui.button("Down").clicked(),
                                             ui.button("Delete").clicked(),
-                                            ui.button("Save").clicked(),
+                                            {
+                                                let save = ui.button("Save");
+
+                                                if index == 0 {
+                                                    save.on_hover_text("Ctrl+S").clicked()
+                                                } else {
+                                                    save.clicked()
+                                                }
+                                            },
                                             {
                                                 if rule_builder.is_none() {
                                                     ui.button("Add Rule").clicked()
@@ -128,7 +1465,7 @@ impl Page for BuildFactoryPage {
                                 }
 
                                 if save_clicked {
-                                    save_rule_list(&self.world, &rule_list, &self.path_field);
+                                    save = Some(index);
                                 }
 
                                 if add_rule {
@@ -136,13 +1473,17 @@ impl Page for BuildFactoryPage {
                                 }
 
                                 if if let Some(rule_builder) = rule_builder.as_mut() {
-                                    rule_builder.show(&self.world, ui);
+                                    let enter = rule_builder.show(&self.world, ui);
 
                                     let (cancel, add) = ui
                                         .horizontal(|ui| {
                                             (
                                                 ui.button("Cancel").clicked(),
-                                                ui.button("Add").clicked(),
+                                                ui.button("Add")
+                                                    .on_hover_text(
+                                                        "Or press Enter in the rate to add and start the next rule",
+                                                    )
+                                                    .clicked(),
                                             )
                                         })
                                         .inner;
@@ -153,12 +1494,21 @@ impl Page for BuildFactoryPage {
                                         }
 
                                         if add {
-                                            if let Some(rule) = rule_builder.build() {
+                                            if let Some(rule) = rule_builder.build(time_base(ui)) {
                                                 rule_list.rules.push(rule);
                                                 break 'b true;
                                             }
                                         }
 
+                                        // enter keeps the builder open for the next rule
+                                        if enter {
+                                            if let Some(rule) = rule_builder.build(time_base(ui)) {
+                                                rule_list.rules.push(rule);
+                                                rule_builder.selected_variable = None;
+                                                rule_builder.focus_variable = true;
+                                            }
+                                        }
+
                                         false
                                     }
                                 } else {
@@ -167,48 +1517,168 @@ impl Page for BuildFactoryPage {
                                     *rule_builder = None;
                                 }
 
+                                let time_base = time_base(ui);
+
+                                // rules are only ever added at the end, so new rules start unselected
+                                let selected = self.selected_rules.entry(*ui_id).or_default();
+                                selected.resize(rule_list.rules.len(), false);
+
+                                if selected.contains(&true) {
+                                    let (delete_selected, unconstrain_selected) = ui
+                                        .horizontal(|ui| {
+                                            (
+                                                ui.button("Delete selected").clicked(),
+                                                ui.button("Set unconstrained").clicked(),
+                                            )
+                                        })
+                                        .inner;
+
+                                    if unconstrain_selected {
+                                        for (rule, _) in rule_list
+                                            .rules
+                                            .iter_mut()
+                                            .zip(selected.iter())
+                                            .filter(|(_, selected)| **selected)
+                                        {
+                                            rule.constraint = Constraint::Unconstrained;
+                                        }
+                                    }
+
+                                    if delete_selected {
+                                        let mut selected_iter = selected.iter();
+                                        rule_list
+                                            .rules
+                                            .retain(|_| !selected_iter.next().unwrap_or(&false));
+                                        selected.clear();
+                                        selected.resize(rule_list.rules.len(), false);
+                                    }
+                                }
+
+                                let mut rule_index = 0;
+                                let mut kept_selection = Vec::new();
+
                                 rule_list.rules.retain(|rule| {
-                                    ui.horizontal(|ui| {
-                                        let remove = ui.button("Edit").clicked();
+                                    let keep = ui
+                                        .horizontal(|ui| {
+                                            ui.checkbox(&mut selected[rule_index], "");
 
-                                        let mut rule_builder_rate = None;
+                                            let remove = ui.button("Edit").clicked();
 
-                                        ui.label(format!(
-                                            "{} {}",
-                                            self.world.name_of_variable(rule.variable),
-                                            match rule.constraint {
-                                                Constraint::Less(rate) => {
-                                                    rule_builder_rate = Some(rate);
-                                                    format!("less than {}", rate)
-                                                }
-                                                Constraint::Equal(rate) => {
-                                                    rule_builder_rate = Some(rate);
-                                                    format!("equal to {}", rate)
+                                            let mut rule_builder_rate = None;
+
+                                            // resource rates are shown in the time base, recipe rates are machine counts
+                                            let suffix = match rule.variable {
+                                                VariableId::Resource(resource) => {
+                                                    format!(
+                                                        " {}",
+                                                        rate_unit(ui, &self.world, Some(resource))
+                                                    )
                                                 }
-                                                Constraint::Greater(rate) => {
-                                                    rule_builder_rate = Some(rate);
-                                                    format!("greater than {}", rate)
+                                                VariableId::Recipe(_) => String::new(),
+                                            };
+
+                                            ui.label(format!(
+                                                "{}{} {}",
+                                                match (rule.soft, rule.priority) {
+                                                    (false, _) => String::new(),
+                                                    (true, 0) => "soft: ".into(),
+                                                    (true, priority) => {
+                                                        format!("soft, priority {}: ", priority)
+                                                    }
+                                                },
+                                                self.world.name_of_variable(rule.variable),
+                                                match rule.constraint {
+                                                    Constraint::Less(rate) => {
+                                                        let rate = display_rule_rate(
+                                                            rule.variable,
+                                                            rate,
+                                                            time_base,
+                                                        );
+                                                        rule_builder_rate = Some(rate);
+                                                        format!("less than {}{}", with_separators(ui, rate.to_string()), suffix)
+                                                    }
+                                                    Constraint::Equal(rate) => {
+                                                        let rate = display_rule_rate(
+                                                            rule.variable,
+                                                            rate,
+                                                            time_base,
+                                                        );
+                                                        rule_builder_rate = Some(rate);
+                                                        format!("equal to {}{}", with_separators(ui, rate.to_string()), suffix)
+                                                    }
+                                                    Constraint::Greater(rate) => {
+                                                        let rate = display_rule_rate(
+                                                            rule.variable,
+                                                            rate,
+                                                            time_base,
+                                                        );
+                                                        rule_builder_rate = Some(rate);
+                                                        format!("greater than {}{}", with_separators(ui, rate.to_string()), suffix)
+                                                    }
+                                                    Constraint::Unconstrained =>
+                                                        "unconstrained".into(),
                                                 }
-                                                Constraint::Unconstrained =>
-                                                    format!("unconstrained"),
+                                            ));
+
+                                            if remove {
+                                                *rule_builder = Some(RuleBuilder {
+                                                    selected_variable: Some(rule.variable),
+                                                    constraint: rule.constraint,
+                                                    rate: rule_builder_rate.unwrap_or(0.),
+                                                    focus_variable: true,
+                                                    producers_of: None,
+                                                    soft: rule.soft,
+                                                    priority: rule.priority,
+                                                })
                                             }
-                                        ));
 
-                                        if remove {
-                                            *rule_builder = Some(RuleBuilder {
-                                                selected_variable: Some(rule.variable),
-                                                constraint: rule.constraint,
-                                                rate: format!(
-                                                    "{}",
-                                                    rule_builder_rate.unwrap_or(0.)
-                                                ),
-                                            })
-                                        }
+                                            !remove
+                                        })
+                                        .inner;
 
-                                        !remove
-                                    })
-                                    .inner
+                                    if keep {
+                                        kept_selection.push(selected[rule_index]);
+                                    }
+
+                                    rule_index += 1;
+
+                                    keep
                                 });
+
+                                *selected = kept_selection;
+
+                                let mut remove_basket = None;
+
+                                for (basket_index, basket) in rule_list.baskets.iter().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        if ui.button("Delete").clicked() {
+                                            remove_basket = Some(basket_index);
+                                        }
+
+                                        let resources: Vec<_> = basket
+                                            .resources
+                                            .iter()
+                                            .map(|&(resource, amount)| {
+                                                format!(
+                                                    "{} {}",
+                                                    format_number(ui, amount),
+                                                    self.world.name_of_resource(resource)
+                                                )
+                                            })
+                                            .collect();
+
+                                        ui.label(format!(
+                                            "basket of {} at {} {}",
+                                            resources.join(", "),
+                                            format_number(ui, time_base.to_display(basket.scale)),
+                                            rate_unit(ui, &self.world, None)
+                                        ));
+                                    });
+                                }
+
+                                if let Some(basket_index) = remove_basket {
+                                    rule_list.baskets.remove(basket_index);
+                                }
                             });
                         });
                     }
@@ -229,29 +1699,128 @@ impl Page for BuildFactoryPage {
                         self.rule_lists.swap(down, down + 1);
                     }
 
+                    if let Some(save) = save {
+                        self.rule_list_feedback =
+                            save_feedback(self.save_rule_list(&self.rule_lists[save].1));
+                    }
+
                     if let Some(delete) = delete {
-                        self.rule_lists.remove(delete);
+                        let (ui_id, _, _) = self.rule_lists.remove(delete);
+                        self.selected_rules.remove(&ui_id);
                     }
                 });
+
+                ui.separator();
+
+                ui.push_id("Allowed Recipes", |ui| {
+                    ui.collapsing("Recipes", |ui| {
+                        ScrollArea::new([false, true]).show(ui, |ui| {
+                            if recipe_checkboxes(
+                                ui,
+                                &self.world,
+                                &self.buildings,
+                                &mut self.allowed_recipes,
+                            ) {
+                                self.recipes_changed = true;
+                            }
+                        });
+                    });
+                });
+
+                ui.push_id("Required Recipes", |ui| {
+                    ui.collapsing("Require", |ui| {
+                        ui.label("Checked recipes always run, even if the solver would skip them.");
+
+                        ScrollArea::new([false, true]).show(ui, |ui| {
+                            if recipe_checkboxes(
+                                ui,
+                                &self.world,
+                                &self.buildings,
+                                &mut self.required_recipes,
+                            ) {
+                                self.recipes_changed = true;
+                            }
+                        });
+                    });
+                });
             });
         });
 
         ui.push_id("Optimization", |ui| {
-            let mut collumn = available_space.translate(Vec2::new(collumn_width, 0.));
-            collumn.set_width(collumn_width);
-            ui.allocate_ui_at_rect(collumn, |ui| {
+            ui.allocate_ui_at_rect(optimization_collumn, |ui| {
                 ui.heading("Optimization");
+                ui.label("The objective is the weighted sum of all entries.");
+
+                // editing the entries of a preset makes them custom
+                if let Some(optimizations) = self.preset_optimizations(self.preset) {
+                    if optimizations != self.valid_optimizations()
+                        || (self.preset == ObjectivePreset::MinimizeRaw && !self.minimize_raw)
+                    {
+                        self.preset = ObjectivePreset::Custom;
+                    }
+                }
+
+                let mut selected_preset = self.preset;
+
+                ComboBox::from_label("Preset")
+                    .selected_text(selected_preset.name())
+                    .show_ui(ui, |ui| {
+                        for preset in ObjectivePreset::ALL {
+                            let available = preset == ObjectivePreset::Custom
+                                || self.preset_optimizations(preset).is_some();
+
+                            ui.add_enabled_ui(available, |ui| {
+                                ui.selectable_value(&mut selected_preset, preset, preset.name());
+                            });
+                        }
+                    })
+                    .response
+                    .on_hover_text("Fill in the entries for a common objective");
+
+                if selected_preset != self.preset {
+                    if let Some(optimizations) = self.preset_optimizations(selected_preset) {
+                        self.set_optimizations(optimizations);
+
+                        if selected_preset == ObjectivePreset::MinimizeRaw {
+                            self.minimize_raw = true;
+                        }
+                    }
+
+                    self.preset = selected_preset;
+                }
+                ui.checkbox(
+                    &mut self.minimize_raw,
+                    "Minimize raw resources when there are no entries",
+                );
+                ui.checkbox(&mut self.sink_points, "Maximize sink points")
+                    .on_hover_text("Lets surplus resources with a point value be sunk");
+                ui.checkbox(&mut self.balance_outputs, "Balance outputs").on_hover_text(
+                    "Maximize the smallest of the entries instead of their weighted sum, weights and tiers are ignored",
+                );
+                ui.add(
+                    Slider::new(&mut self.prune_threshold, 0.0..=1.0)
+                        .text("Prune recipes below")
+                        .suffix(" machines"),
+                )
+                .on_hover_text(
+                    "Re-solves without recipes using fewer machines than this, to reduce the number of distinct recipes",
+                );
+                ui.checkbox(&mut self.show_marginal, "Show marginal recipes")
+                    .on_hover_text("Keep recipes whose rate rounds to zero");
+                ui.checkbox(&mut self.strict_balance, "Strict balance").on_hover_text(
+                    "Allow surplus of made resources and reject solutions left short by rounding",
+                );
 
                 if ui.button("New").clicked() {
                     self.optimizations
-                        .push((self.optimization_id_incrementor, None, "0".into()));
+                        .push((self.optimization_id_incrementor, None, 0., 1));
                     self.optimization_id_incrementor += 1;
                 }
 
                 ScrollArea::new([false, true]).show(ui, |ui| {
                     let mut remove = None;
 
-                    for (index, (ui_id, selected_variable, bias)) in
+                    for (index, (ui_id, selected_variable, bias, tier)) in
                         self.optimizations.iter_mut().enumerate()
                     {
                         ui.push_id(ui_id, |ui| {
@@ -268,138 +1837,774 @@ impl Page for BuildFactoryPage {
                                             remove = Some(index);
                                         };
 
-                                        ui.label(RichText::new("Resources").strong());
-                                        for (resource_id, resource) in
-                                            self.world.resources.iter().enumerate()
-                                        {
+                                        variable_options(ui, &self.world, selected_variable, None);
+                                    });
+
+                                ui.add(DragValue::new(bias).speed(0.1));
+
+                                ui.add(DragValue::new(tier).clamp_range(1..=9).prefix("tier "))
+                                    .on_hover_text("Lower tiers are optimized first");
+                            });
+                        });
+                    }
+
+                    if let Some(index) = remove {
+                        self.optimizations.remove(index);
+                    }
+                });
+
+                ui.separator();
+                ui.heading("Recipe Caps");
+
+                if ui.button("New Cap").clicked() {
+                    self.recipe_caps
+                        .push((self.recipe_cap_id_incrementor, None, "0".into()));
+                    self.recipe_cap_id_incrementor += 1;
+                }
+
+                ui.push_id("Recipe Caps", |ui| {
+                    ScrollArea::new([false, true]).show(ui, |ui| {
+                        let mut remove = None;
+
+                        for (index, (ui_id, selected_recipe, max)) in
+                            self.recipe_caps.iter_mut().enumerate()
+                        {
+                            ui.push_id(ui_id, |ui| {
+                                ui.horizontal(|ui| {
+                                    let selected_text = match selected_recipe {
+                                        Some(recipe) => self.world.name_of_recipe(*recipe),
+                                        None => "...",
+                                    };
+
+                                    ComboBox::from_label("")
+                                        .selected_text(selected_text)
+                                        .show_ui(ui, |ui| {
+                                            recipe_options(ui, &self.world, selected_recipe);
+                                        });
+
+                                    ui.label("at most");
+                                    ui.text_edit_singleline(max);
+
+                                    if parse_number(max).is_none() {
+                                        ui.label("Invalid number");
+                                    }
+
+                                    if ui.button("Delete").clicked() {
+                                        remove = Some(index);
+                                    }
+                                });
+                            });
+                        }
+
+                        if let Some(index) = remove {
+                            self.recipe_caps.remove(index);
+                        }
+                    });
+                });
+
+                ui.separator();
+                ui.heading("Imports");
+
+                if ui.button("New Import").clicked() {
+                    self.imports
+                        .push((self.import_id_incrementor, None, "0".into()));
+                    self.import_id_incrementor += 1;
+                }
+
+                ui.push_id("Imports", |ui| {
+                    ScrollArea::new([false, true]).show(ui, |ui| {
+                        let mut remove = None;
+
+                        for (index, (ui_id, selected_resource, cap)) in
+                            self.imports.iter_mut().enumerate()
+                        {
+                            ui.push_id(ui_id, |ui| {
+                                ui.horizontal(|ui| {
+                                    let selected_text = match selected_resource {
+                                        Some(resource) => self.world.name_of_resource(*resource),
+                                        None => "...",
+                                    };
+
+                                    ComboBox::from_label("")
+                                        .selected_text(selected_text)
+                                        .show_ui(ui, |ui| {
+                                            resource_options(ui, &self.world, selected_resource);
+                                        });
+
+                                    ui.label("up to");
+                                    ui.text_edit_singleline(cap);
+                                    ui.label(rate_unit(ui, &self.world, *selected_resource));
+
+                                    if parse_number(cap).is_none() {
+                                        ui.label("Invalid number");
+                                    }
+
+                                    if ui.button("Delete").clicked() {
+                                        remove = Some(index);
+                                    }
+                                });
+                            });
+                        }
+
+                        if let Some(index) = remove {
+                            self.imports.remove(index);
+                        }
+                    });
+                });
+
+                ui.separator();
+                ui.heading("Ratios");
+
+                if ui.button("New Ratio").clicked() {
+                    self.ratios
+                        .push((self.ratio_id_incrementor, None, None, "1".into()));
+                    self.ratio_id_incrementor += 1;
+                }
+
+                ui.push_id("Ratios", |ui| {
+                    ScrollArea::new([false, true]).show(ui, |ui| {
+                        let mut remove = None;
+
+                        for (index, (ui_id, a, b, factor)) in self.ratios.iter_mut().enumerate() {
+                            ui.push_id(ui_id, |ui| {
+                                ui.horizontal(|ui| {
+                                    for (id, selected_variable) in [("A", &mut *a), ("B", &mut *b)]
+                                    {
+                                        let selected_text = match selected_variable {
+                                            Some(variable) => {
+                                                self.world.name_of_variable(*variable)
+                                            }
+                                            None => "...".into(),
+                                        };
+
+                                        ComboBox::from_id_source(id)
+                                            .selected_text(selected_text)
+                                            .show_ui(ui, |ui| {
+                                                variable_options(
+                                                    ui,
+                                                    &self.world,
+                                                    selected_variable,
+                                                    None,
+                                                );
+                                            });
+
+                                        if id == "A" {
+                                            ui.label("=");
+                                            ui.text_edit_singleline(factor);
+                                            ui.label("×");
+                                        }
+                                    }
+
+                                    if parse_number(factor).is_none() {
+                                        ui.label("Invalid number");
+                                    }
+
+                                    if ui.button("Delete").clicked() {
+                                        remove = Some(index);
+                                    }
+                                });
+                            });
+                        }
+
+                        if let Some(index) = remove {
+                            self.ratios.remove(index);
+                        }
+                    });
+                });
+
+                ui.separator();
+                ui.heading("Percentages");
+
+                if ui.button("New Percentage").clicked() {
+                    self.percentages
+                        .push((self.percentage_id_incrementor, None, None, "10".into()));
+                    self.percentage_id_incrementor += 1;
+                }
+
+                ui.push_id("Percentages", |ui| {
+                    ScrollArea::new([false, true]).show(ui, |ui| {
+                        let mut remove = None;
+
+                        for (index, (ui_id, a, b, percentage)) in
+                            self.percentages.iter_mut().enumerate()
+                        {
+                            ui.push_id(ui_id, |ui| {
+                                ui.horizontal(|ui| {
+                                    for (id, selected_variable) in [("A", &mut *a), ("B", &mut *b)]
+                                    {
+                                        let selected_text = match selected_variable {
+                                            Some(variable) => {
+                                                self.world.name_of_variable(*variable)
+                                            }
+                                            None => "...".into(),
+                                        };
+
+                                        ComboBox::from_id_source(id)
+                                            .selected_text(selected_text)
+                                            .show_ui(ui, |ui| {
+                                                variable_options(
+                                                    ui,
+                                                    &self.world,
+                                                    selected_variable,
+                                                    None,
+                                                );
+                                            });
+
+                                        if id == "A" {
+                                            ui.label("at most");
+                                            ui.text_edit_singleline(percentage);
+                                            ui.label("% of");
+                                        }
+                                    }
+
+                                    if parse_number(percentage).is_none() {
+                                        ui.label("Invalid number");
+                                    }
+
+                                    if ui.button("Delete").clicked() {
+                                        remove = Some(index);
+                                    }
+                                });
+                            });
+                        }
+
+                        if let Some(index) = remove {
+                            self.percentages.remove(index);
+                        }
+                    });
+                });
+
+                ui.separator();
+                ui.heading("Tag Rules");
+
+                if ui.button("New Tag Rule").clicked() {
+                    self.tag_rules.push((
+                        self.tag_rule_id_incrementor,
+                        None,
+                        Constraint::Less(0.),
+                        "0".into(),
+                    ));
+                    self.tag_rule_id_incrementor += 1;
+                }
+
+                ui.push_id("Tag Rules", |ui| {
+                    ScrollArea::new([false, true]).show(ui, |ui| {
+                        let mut remove = None;
+
+                        for (index, (ui_id, selected_tag, constraint, value)) in
+                            self.tag_rules.iter_mut().enumerate()
+                        {
+                            ui.push_id(ui_id, |ui| {
+                                ui.horizontal(|ui| {
+                                    let selected_text = match selected_tag {
+                                        Some(tag) => tag.as_str(),
+                                        None => "...",
+                                    };
+
+                                    ComboBox::from_id_source("Tag")
+                                        .selected_text(selected_text)
+                                        .show_ui(ui, |ui| {
+                                            for tag in self.tags.iter() {
+                                                ui.selectable_value(
+                                                    selected_tag,
+                                                    Some(tag.clone()),
+                                                    tag,
+                                                );
+                                            }
+                                        });
+
+                                    ui.label("machines");
+
+                                    ComboBox::from_id_source("Constraint")
+                                        .selected_text(match constraint {
+                                            Constraint::Less(_) => "at most",
+                                            Constraint::Equal(_) => "exactly",
+                                            _ => "at least",
+                                        })
+                                        .show_ui(ui, |ui| {
                                             ui.selectable_value(
-                                                selected_variable,
-                                                Some(VariableId::Resource(ResourceId(resource_id))),
-                                                &resource.name,
+                                                constraint,
+                                                Constraint::Less(0.),
+                                                "at most",
                                             );
-                                        }
+                                            ui.selectable_value(
+                                                constraint,
+                                                Constraint::Equal(0.),
+                                                "exactly",
+                                            );
+                                            ui.selectable_value(
+                                                constraint,
+                                                Constraint::Greater(0.),
+                                                "at least",
+                                            );
+                                        });
+
+                                    ui.text_edit_singleline(value);
+
+                                    if parse_number(value).is_none() {
+                                        ui.label("Invalid number");
+                                    }
+
+                                    if ui.button("Delete").clicked() {
+                                        remove = Some(index);
+                                    }
+                                });
+                            });
+                        }
+
+                        if let Some(index) = remove {
+                            self.tag_rules.remove(index);
+                        }
+                    });
+                });
+            });
+        });
+
+        ui.push_id("Output", |ui| {
+            ui.allocate_ui_at_rect(output_collumn, |ui| {
+                ui.heading("Output");
+
+                let (solve, check, solve_each, compare) = ui
+                    .horizontal(|ui| {
+                        let solve = ui.button("Solve").on_hover_text("Ctrl+Enter").clicked()
+                            || shortcut_pressed(ui, Key::Enter);
+
+                        let check = ui
+                            .button("Check Feasible")
+                            .on_hover_text("Solve the rules without optimizing anything")
+                            .clicked();
+
+                        let solve_each = ui
+                            .button("Solve All")
+                            .on_hover_text(
+                                "Solve each rule list on its own with the rest of the page's settings",
+                            )
+                            .clicked();
+
+                        let compare = ui
+                            .button("Compare Objectives")
+                            .on_hover_text(
+                                "Solve the rules minimizing raw resources, power and machines in turn",
+                            )
+                            .clicked();
+
+                        if let Some(problem) = &self.last_problem {
+                            if ui
+                                .button("Show LP")
+                                .on_hover_text("The linear program of the last solve")
+                                .clicked()
+                            {
+                                self.lp_description = Some(problem.describe(&self.world));
+                            }
+                        }
+
+                        (solve, check, solve_each, compare)
+                    })
+                    .inner;
+
+                if !self.batch_solutions.is_empty() {
+                    self.batch_solutions(ui);
+                }
+
+                if !self.comparison.is_empty() {
+                    self.comparison(ui);
+                }
+
+                if self.recipes_changed {
+                    ui.label(
+                        RichText::new("The allowed recipes changed, solve again to apply them")
+                            .color(ui.visuals().warn_fg_color),
+                    );
+                }
+
+                if let Some(feasibility) = &self.feasibility {
+                    ui.label(feasibility);
+                }
+
+                if let Some(factory) = self.what_if(ui) {
+                    self.set_solution(factory);
+                }
+
+                if self.pending.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Solving...");
+
+                        if ui.button("Cancel").clicked() {
+                            self.pending = None;
+                        }
+                    });
+                }
+
+                'cancel: {
+                    if solve || check || solve_each || compare {
+                        let mut problem = Problem::default();
+
+                        for (_, rule_list, _) in self.rule_lists.iter() {
+                            problem.rules.extend(rule_list.all_rules());
+                        }
+
+                        let mut tiers: Vec<(u32, Vec<(VariableId, f64)>)> = Vec::new();
+
+                        for &(_, variable, rate, tier) in self.optimizations.iter() {
+                            let Some(variable) = variable else {
+                                continue;
+                            };
+
+                            problem.optimizations.push((variable, rate));
+
+                            match tiers.iter_mut().find(|&&mut (other, _)| other == tier) {
+                                Some((_, entries)) => entries.push((variable, rate)),
+                                None => tiers.push((tier, vec![(variable, rate)])),
+                            }
+                        }
+
+                        tiers.sort_by_key(|(tier, _)| *tier);
+
+                        if self.balance_outputs {
+                            let outputs = problem
+                                .optimizations
+                                .drain(..)
+                                .map(|(variable, _)| variable)
+                                .collect();
+                            problem.maximize_min(outputs);
+                            tiers.clear();
+                        }
 
-                                        ui.label(RichText::new("Recipes").strong());
-                                        for (recipe_id, recipe) in
-                                            self.world.recipes.iter().enumerate()
-                                        {
-                                            ui.selectable_value(
-                                                selected_variable,
-                                                Some(VariableId::Recipe(RecipeId(recipe_id))),
-                                                &recipe.name,
-                                            );
-                                        }
-                                    });
+                        problem.any_feasible = !self.minimize_raw;
+                        problem.sink_points = self.sink_points;
 
-                                ui.text_edit_singleline(bias);
+                        if let Err(response) = self.add_constraints(&mut problem, time_base(ui)) {
+                            self.solution = Err(response);
+                            break 'cancel;
+                        }
 
-                                if bias.parse::<f64>().is_err() {
-                                    ui.label("Invalid number");
-                                }
+                        if solve_each {
+                            self.solve_each(&problem);
+                            break 'cancel;
+                        }
+
+                        if compare {
+                            self.compare_objectives(&problem);
+                            break 'cancel;
+                        }
+
+                        if check {
+                            // with nothing to optimize the solver stops at the first feasible point
+                            problem.optimizations.clear();
+                            problem.any_feasible = true;
+                            problem.sink_points = false;
+                            problem.balanced.clear();
+
+                            // an unbounded problem still has feasible points
+                            self.feasibility = Some(match problem.solve(&self.world) {
+                                Ok(_) => "Feasible".into(),
+                                Err(response) if response == "Unbounded" => "Feasible".into(),
+                                Err(response) => response,
                             });
-                        });
+
+                            break 'cancel;
+                        }
+
+                        self.feasibility = None;
+
+                        self.solve(
+                            problem,
+                            tiers.into_iter().map(|(_, entries)| entries).collect(),
+                        );
                     }
+                }
 
-                    if let Some(index) = remove {
-                        self.optimizations.remove(index);
+                let mut explain = None;
+                let mut input_rules = None;
+
+                // hovering is only known after drawing, so highlight what was hovered last frame
+                let hovered = self.hovered.take();
+                let highlight = |ui: &Ui, text: RichText, variable: VariableId| {
+                    if hovered.is_some_and(|hovered| linked(&self.world, hovered, variable)) {
+                        text.background_color(ui.visuals().selection.bg_fill)
+                    } else {
+                        text
                     }
-                });
-            });
-        });
+                };
 
-        ui.push_id("Output", |ui| {
-            let mut collumn = available_space.translate(Vec2::new(collumn_width * 2., 0.));
-            collumn.set_width(collumn_width);
-            ui.allocate_ui_at_rect(collumn, |ui| {
-                ui.heading("Output");
+                ScrollArea::new([false, true]).show(ui, |ui| match &self.solution {
+                    Ok((factory, net_resources, objective)) => {
+                        for &variable in self.optimized.iter() {
+                            let value = factory.value_of(net_resources, variable);
 
-                let solve = ui.button("Solve").clicked();
+                            let value = match variable {
+                                VariableId::Resource(resource) => {
+                                    format_rate(ui, &self.world, resource, value)
+                                }
+                                VariableId::Recipe(_) => {
+                                    format!("{} machines", format_number(ui, value))
+                                }
+                            };
 
-                'cancel: {
-                    if solve {
-                        let mut problem = Problem::default();
+                            ui.label(
+                                RichText::new(format!(
+                                    "Optimized: {} = {}",
+                                    self.world.name_of_variable(variable),
+                                    value
+                                ))
+                                .strong()
+                                .background_color(ui.visuals().selection.bg_fill),
+                            );
+                        }
 
-                        for (_, rule_list, _) in self.rule_lists.iter() {
-                            for rule in rule_list.rules.iter() {
-                                problem.rules.push(*rule);
+                        ui.horizontal(|ui| {
+                            if ui.button("Edit").clicked() {
+                                edit_factory = Some(factory.clone());
                             }
-                        }
 
-                        for (_, variable, rate) in self.optimizations.iter() {
-                            let Some(variable) = variable else {
+                            copy_factory_text(ui, &self.world, factory);
+                            export_sankey(ui, &self.world, factory);
+
+                            if ui
+                                .button("Add Input Rules")
+                                .on_hover_text(
+                                    "Adds a rule list capping each raw resource at its solved consumption",
+                                )
+                                .clicked()
+                            {
+                                input_rules = Some(input_rule_list(&self.world, net_resources));
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Objective: {}", format_number(ui, *objective)));
+
+                            if let Some(solve_time) = self.solve_time {
+                                ui.label(format!("Solved in {} ms", solve_time.as_millis()));
+                            }
+                        });
+
+                        // how far the solver had to break each soft rule
+                        for rule in self.last_problem.iter().flat_map(|problem| &problem.rules) {
+                            if !rule.soft {
                                 continue;
-                            };
+                            }
 
-                            let Ok(rate) = rate.parse() else {
-                                self.solution =
-                                    Err(format!("Invalid number \"{}\" in optimization", rate));
-                                break 'cancel;
+                            let violation = rule.violation(factory.value_of(net_resources, rule.variable));
+
+                            if violation < 1e-6 {
+                                continue;
+                            }
+
+                            let unit = match rule.variable {
+                                VariableId::Resource(resource) => {
+                                    rate_unit(ui, &self.world, Some(resource))
+                                }
+                                VariableId::Recipe(_) => "machines".into(),
                             };
 
-                            problem.optimizations.push((*variable, rate));
+                            ui.label(
+                                RichText::new(format!(
+                                    "The soft rule on {} is off by {} {}",
+                                    self.world.name_of_variable(rule.variable),
+                                    format_number(
+                                        ui,
+                                        display_rule_rate(rule.variable, violation, time_base(ui))
+                                    ),
+                                    unit
+                                ))
+                                .color(ui.visuals().warn_fg_color),
+                            );
+                        }
+
+                        if let Some(previous_factory) = &self.previous_factory {
+                            ui.collapsing("Changes since last solve", |ui| {
+                                for (recipe, old_rate, new_rate) in previous_factory.diff(factory) {
+                                    if old_rate == new_rate {
+                                        continue;
+                                    }
+
+                                    let color = if new_rate > old_rate {
+                                        Color32::GREEN
+                                    } else {
+                                        Color32::RED
+                                    };
+
+                                    ui.label(
+                                        RichText::new(format!(
+                                            "{} {} -> {} machines",
+                                            self.world.name_of_recipe(recipe),
+                                            format_number(ui, old_rate),
+                                            format_number(ui, new_rate)
+                                        ))
+                                        .color(color),
+                                    );
+                                }
+                            });
                         }
 
-                        self.solution = match problem.solve(&self.world) {
-                            Err(response) => Err(response),
-                            Ok(factory) => {
-                                let resources = factory.net_resources(&self.world);
-                                Ok((factory, resources))
+                        ui.collapsing("Net Rate Chart", |ui| {
+                            if let Some(resource) = net_rate_chart(ui, &self.world, net_resources) {
+                                self.scroll_to_resource = Some(resource);
                             }
-                        };
-                    }
-                }
+                        });
 
-                ScrollArea::new([false, true]).show(ui, |ui| match &self.solution {
-                    Ok((factory, net_resources)) => {
-                        if ui.button("Edit").clicked() {
-                            edit_factory = Some(factory.clone());
+                        let mut net_resources_header = CollapsingHeader::new("Net Resources");
+
+                        if self.scroll_to_resource.is_some() {
+                            net_resources_header = net_resources_header.open(Some(true));
                         }
 
-                        ui.collapsing("Net Resources", |ui| {
+                        net_resources_header.show(ui, |ui| {
                             for (resource_index, (rate, recipes)) in
                                 net_resources.resources.iter().enumerate()
                             {
-                                if recipes.len() == 0 {
+                                if recipes.is_empty() {
                                     continue;
                                 }
 
                                 let resource_name =
                                     self.world.name_of_resource(ResourceId(resource_index));
 
-                                ui.label(
-                                    RichText::new(format!(
-                                        "{} net {:.0000001} /min",
-                                        resource_name, rate
-                                    ))
-                                    .strong(),
-                                );
+                                ui.horizontal(|ui| {
+                                    resource_swatch(ui, &self.world, ResourceId(resource_index));
+                                    let resource = ResourceId(resource_index).variable_id();
+                                    let text = net_rate_color(
+                                        format!(
+                                            "{} net {}",
+                                            resource_name,
+                                            format_rate(
+                                                ui,
+                                                &self.world,
+                                                ResourceId(resource_index),
+                                                *rate
+                                            )
+                                        ),
+                                        *rate,
+                                    )
+                                    .strong();
+
+                                    let response = ui.label(highlight(ui, text, resource));
+
+                                    if response.hovered() {
+                                        self.hovered = Some(resource);
+                                    }
+
+                                    if self.scroll_to_resource == Some(ResourceId(resource_index)) {
+                                        response.scroll_to_me(Some(Align::Center));
+                                        self.scroll_to_resource = None;
+                                    }
+
+                                    if let Some(usage) =
+                                        self.world.node_usage(ResourceId(resource_index), *rate)
+                                    {
+                                        ui.label(usage);
+                                    }
+                                });
 
-                                for &(recipe, rate) in recipes.iter() {
+                                for (recipe, rate) in net_resources
+                                    .sorted_contributors(&self.world, ResourceId(resource_index))
+                                {
                                     let recipe_name = self.world.name_of_recipe(recipe);
 
-                                    ui.label(format!("  {} {:.0000001} /min", recipe_name, rate));
+                                    let mut text =
+                                        format!(
+                                            "  {} {}",
+                                            recipe_name,
+                                            format_rate(
+                                                ui,
+                                                &self.world,
+                                                ResourceId(resource_index),
+                                                rate
+                                            )
+                                        );
+
+                                    if let Some(warning) = self
+                                        .world
+                                        .throughput_warning(ResourceId(resource_index), rate)
+                                    {
+                                        text.push_str(&format!(" ({})", warning));
+                                    }
+
+                                    ui.label(text);
                                 }
                             }
                         });
 
+                        factory_summary(ui, &self.world, factory);
+
                         ui.collapsing("Recipes", |ui| {
+                            if !self.pinned.is_empty() && ui.button("Unpin All").clicked() {
+                                self.pinned.clear();
+                            }
+
                             for &(recipe, rate) in factory.recipes.iter() {
-                                let recipe_name = self.world.name_of_recipe(recipe);
+                                let recipe_name = self.world.recipe_label(recipe);
 
                                 ui.horizontal(|ui| {
-                                    ui.label(RichText::new(recipe_name).strong());
-                                    ui.label(format!("{:.0000001} machines", rate));
+                                    let mut pinned =
+                                        self.pinned.iter().any(|&(other, _)| other == recipe);
+
+                                    if ui
+                                        .checkbox(&mut pinned, "")
+                                        .on_hover_text(
+                                            "Pin at this machine count for the next solve, \
+                                            conflicting rules make the problem infeasible",
+                                        )
+                                        .changed()
+                                    {
+                                        self.pinned.retain(|&(other, _)| other != recipe);
+
+                                        if pinned {
+                                            self.pinned.push((recipe, rate));
+                                        }
+                                    }
+
+                                    let text = RichText::new(recipe_name).strong();
+
+                                    if ui
+                                        .label(highlight(ui, text, recipe.variable_id()))
+                                        .hovered()
+                                    {
+                                        self.hovered = Some(recipe.variable_id());
+                                    }
+
+                                    ui.label(format!("{} machines", format_number(ui, rate)));
+
+                                    if self.last_problem.is_some()
+                                        && ui
+                                            .button("Why?")
+                                            .on_hover_text("Solve again without this recipe")
+                                            .clicked()
+                                    {
+                                        explain = Some(recipe);
+                                    }
                                 });
 
+                                if self
+                                    .explaining
+                                    .as_ref()
+                                    .is_some_and(|&(other, _)| other == recipe)
+                                {
+                                    ui.label("  Solving without it...");
+                                } else if let Some((_, explanation)) = self
+                                    .explanations
+                                    .iter()
+                                    .find(|&&(other, _)| other == recipe)
+                                {
+                                    ui.label(format!("  {}", explanation));
+                                }
+
                                 for &(resource, resource_rate) in
                                     self.world.recipes[recipe.0].rates.iter()
                                 {
                                     let resource_name = self.world.name_of_resource(resource);
 
                                     ui.label(format!(
-                                        "  {} {:.0000001} /min",
+                                        "  {} {}",
                                         resource_name,
-                                        rate * resource_rate
+                                        format_rate(
+                                            ui,
+                                            &self.world,
+                                            resource,
+                                            rate * resource_rate
+                                        )
                                     ));
                                 }
                             }
@@ -409,9 +2614,34 @@ impl Page for BuildFactoryPage {
                         ui.label(response);
                     }
                 });
+
+                if let Some(rule_list) = input_rules {
+                    self.rule_lists
+                        .push((self.rule_list_id_incrementor, rule_list, None));
+                    self.rule_list_id_incrementor += 1;
+                }
+
+                if let Some(recipe) = explain {
+                    self.explain(recipe);
+                }
             });
         });
 
+        if let Some(description) = &self.lp_description {
+            let mut open = true;
+
+            Window::new("LP")
+                .open(&mut open)
+                .vscroll(true)
+                .show(ui.ctx(), |ui| {
+                    ui.label(RichText::new(description).monospace());
+                });
+
+            if !open {
+                self.lp_description = None;
+            }
+        }
+
         if let Some(factory) = edit_factory {
             return Box::new(EditFactoryPage::new(self.world, factory));
         }
@@ -423,7 +2653,14 @@ impl Page for BuildFactoryPage {
 struct RuleBuilder {
     selected_variable: Option<VariableId>,
     constraint: Constraint,
-    rate: String,
+    rate: f64,
+    /// move keyboard focus to the variable next frame, so tab goes through the controls from the start
+    focus_variable: bool,
+    /// only offer the recipes producing this resource
+    producers_of: Option<ResourceId>,
+    soft: bool,
+    /// soft rules with a higher priority are broken last
+    priority: u32,
 }
 
 impl RuleBuilder {
@@ -431,11 +2668,16 @@ impl RuleBuilder {
         RuleBuilder {
             selected_variable: None,
             constraint: Constraint::Equal(0.),
-            rate: "0".into(),
+            rate: 0.,
+            focus_variable: true,
+            producers_of: None,
+            soft: false,
+            priority: 0,
         }
     }
 
-    fn show(&mut self, world: &World, ui: &mut Ui) {
+    /// returns whether enter was pressed in the rate field to add the rule
+    fn show(&mut self, world: &World, ui: &mut Ui) -> bool {
         ui.label("New Rule:");
 
         let selected_text = match self.selected_variable {
@@ -443,28 +2685,34 @@ impl RuleBuilder {
             None => "...".into(),
         };
 
+        ui.horizontal(|ui| {
+            ui.label("Recipes making");
+
+            let selected_text = match self.producers_of {
+                Some(resource) => world.name_of_resource(resource),
+                None => "anything",
+            };
+
+            ComboBox::from_id_source("Producers Of")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.producers_of, None, "anything");
+                    resource_options(ui, world, &mut self.producers_of);
+                });
+        });
+
         ui.push_id("Variable", |ui| {
-            ComboBox::from_label("")
+            let response = ComboBox::from_label("")
                 .selected_text(selected_text)
                 .show_ui(ui, |ui| {
-                    ui.label(RichText::new("Resources").strong());
-                    for (resource_id, resource) in world.resources.iter().enumerate() {
-                        ui.selectable_value(
-                            &mut self.selected_variable,
-                            Some(VariableId::Resource(ResourceId(resource_id))),
-                            &resource.name,
-                        );
-                    }
+                    variable_options(ui, world, &mut self.selected_variable, self.producers_of);
+                })
+                .response;
 
-                    ui.label(RichText::new("Recipes").strong());
-                    for (recipe_id, recipe) in world.recipes.iter().enumerate() {
-                        ui.selectable_value(
-                            &mut self.selected_variable,
-                            Some(VariableId::Recipe(RecipeId(recipe_id))),
-                            &recipe.name,
-                        );
-                    }
-                });
+            if self.focus_variable {
+                response.request_focus();
+                self.focus_variable = false;
+            }
         });
 
         ui.push_id("Constraint", |ui| {
@@ -491,31 +2739,495 @@ impl RuleBuilder {
             self.constraint,
             Constraint::Less(_) | Constraint::Equal(_) | Constraint::Greater(_)
         ) {
-            ui.horizontal(|ui| {
-                ui.text_edit_singleline(&mut self.rate);
-
-                if self.rate.parse::<f64>().is_err() {
-                    ui.label("Invalid number");
-                }
-            });
+            let response = ui.add(DragValue::new(&mut self.rate));
+
+            ui.checkbox(&mut self.soft, "Soft")
+                .on_hover_text("Let the solver break the rule at a cost instead of failing");
+
+            if self.soft {
+                ui.add(
+                    DragValue::new(&mut self.priority)
+                        .clamp_range(0..=MAX_SOFT_RULE_PRIORITY)
+                        .prefix("priority "),
+                )
+                .on_hover_text(
+                    "Each level makes the rule ten times as costly to break, so higher priorities give way last",
+                );
+            }
+
+            // the drag value gives up focus on the frame enter is pressed while typing in it
+            return (response.has_focus() || response.lost_focus())
+                && ui.input(|input| input.key_pressed(Key::Enter));
         }
+
+        false
     }
 
-    fn build(&self) -> Option<Rule> {
-        let parse_rate = || self.rate.parse().ok();
+    /// builds the rule, converting a resource rate from the time base to per minute
+    fn build(&self, time_base: TimeBase) -> Option<Rule> {
+        let variable = self.selected_variable?;
+
+        let rate = match variable {
+            VariableId::Resource(_) => time_base.from_display(self.rate),
+            VariableId::Recipe(_) => self.rate,
+        };
 
         let constraint = match self.constraint {
-            Constraint::Less(_) => Constraint::Less(parse_rate()?),
-            Constraint::Equal(_) => Constraint::Equal(parse_rate()?),
-            Constraint::Greater(_) => Constraint::Greater(parse_rate()?),
+            Constraint::Less(_) => Constraint::Less(rate),
+            Constraint::Equal(_) => Constraint::Equal(rate),
+            Constraint::Greater(_) => Constraint::Greater(rate),
             Constraint::Unconstrained => Constraint::Unconstrained,
         };
 
-        let variable = self.selected_variable?;
-
         Some(Rule {
             variable,
             constraint,
+            soft: self.soft,
+            priority: self.priority,
+        })
+    }
+}
+
+/// a common objective the optimization entries can be filled from
+#[derive(Clone, Copy, PartialEq)]
+enum ObjectivePreset {
+    /// entries added by hand
+    Custom,
+    MinimizeRaw,
+    MinimizePower,
+    MinimizeMachines,
+    /// maximize the resource picked as the target product
+    MaximizeOutput,
+}
+
+impl ObjectivePreset {
+    const ALL: [ObjectivePreset; 5] = [
+        ObjectivePreset::Custom,
+        ObjectivePreset::MinimizeRaw,
+        ObjectivePreset::MinimizePower,
+        ObjectivePreset::MinimizeMachines,
+        ObjectivePreset::MaximizeOutput,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ObjectivePreset::Custom => "Custom",
+            ObjectivePreset::MinimizeRaw => "Minimize raw resources",
+            ObjectivePreset::MinimizePower => "Minimize power",
+            ObjectivePreset::MinimizeMachines => "Minimize machines",
+            ObjectivePreset::MaximizeOutput => "Maximize selected output",
+        }
+    }
+}
+
+/// sets up a resource target produced by any mix of recipes, with a minimum output from some of them
+///
+/// this maps onto rules the [Problem] already supports, an `Equal` rule on the resource for the target
+/// and a `Greater` rule on each recipe with a minimum, converted from an output rate into machines
+struct BlendBuilder {
+    selected_resource: Option<ResourceId>,
+    rate: String,
+    /// each element is a ui id, a recipe producing the resource and the least of the resource it must make
+    minimums: Vec<(u64, Option<RecipeId>, String)>,
+    minimum_id_incrementor: u64,
+}
+
+impl BlendBuilder {
+    fn new() -> Self {
+        BlendBuilder {
+            selected_resource: None,
+            rate: "0".into(),
+            minimums: Vec::new(),
+            minimum_id_incrementor: 0,
+        }
+    }
+
+    fn show(&mut self, world: &World, ui: &mut Ui) {
+        ui.label("New Blend:");
+
+        ui.horizontal(|ui| {
+            let selected_text = match self.selected_resource {
+                Some(resource) => world.name_of_resource(resource),
+                None => "...",
+            };
+
+            ComboBox::from_id_source("Resource")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    resource_options(ui, world, &mut self.selected_resource);
+                });
+
+            ui.label("equal to");
+            ui.text_edit_singleline(&mut self.rate);
+            ui.label(rate_unit(ui, world, self.selected_resource));
+
+            if parse_number(&self.rate).is_none() {
+                ui.label("Invalid number");
+            }
+        });
+
+        let Some(resource) = self.selected_resource else {
+            return;
+        };
+
+        if ui.button("New Minimum").clicked() {
+            self.minimums
+                .push((self.minimum_id_incrementor, None, "0".into()));
+            self.minimum_id_incrementor += 1;
+        }
+
+        let mut remove = None;
+
+        for (index, (ui_id, selected_recipe, minimum)) in self.minimums.iter_mut().enumerate() {
+            ui.push_id(ui_id, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("at least");
+                    ui.text_edit_singleline(minimum);
+                    ui.label(format!(
+                        "{} from",
+                        rate_unit(ui, world, self.selected_resource)
+                    ));
+
+                    let selected_text = match selected_recipe {
+                        Some(recipe) => world.name_of_recipe(*recipe),
+                        None => "...",
+                    };
+
+                    // only recipes producing the resource can contribute to the blend
+                    ComboBox::from_id_source("Recipe")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for (recipe_id, recipe) in world.recipes.iter().enumerate() {
+                                if output_per_machine(world, RecipeId(recipe_id), resource)
+                                    .is_some()
+                                {
+                                    ui.selectable_value(
+                                        selected_recipe,
+                                        Some(RecipeId(recipe_id)),
+                                        &recipe.name,
+                                    );
+                                }
+                            }
+                        });
+
+                    if parse_number(minimum).is_none() {
+                        ui.label("Invalid number");
+                    }
+
+                    if ui.button("Delete").clicked() {
+                        remove = Some(index);
+                    }
+                });
+            });
+        }
+
+        if let Some(index) = remove {
+            self.minimums.remove(index);
+        }
+    }
+
+    /// builds the rules for the blend, converting rates from the time base to per minute
+    fn build(&self, world: &World, time_base: TimeBase) -> Option<Vec<Rule>> {
+        let resource = self.selected_resource?;
+        let rate = time_base.from_display(parse_number(&self.rate)?);
+
+        let mut rules = vec![Rule {
+            variable: resource.variable_id(),
+            constraint: Constraint::Equal(rate),
+            soft: false,
+            priority: 0,
+        }];
+
+        for (_, recipe, minimum) in self.minimums.iter() {
+            let Some(recipe) = recipe else {
+                continue;
+            };
+
+            let minimum = time_base.from_display(parse_number(minimum)?);
+            let output = output_per_machine(world, *recipe, resource)?;
+
+            rules.push(Rule {
+                variable: recipe.variable_id(),
+                constraint: Constraint::Greater(minimum / output),
+                soft: false,
+                priority: 0,
+            });
+        }
+
+        Some(rules)
+    }
+}
+
+/// a bar per used resource showing its net rate, returns the resource whose bar was clicked
+fn net_rate_chart(ui: &mut Ui, world: &World, net_resources: &NetResources) -> Option<ResourceId> {
+    let time_base = time_base(ui);
+
+    let resources: Vec<(ResourceId, f64)> = net_resources
+        .resources
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, recipes))| !recipes.is_empty())
+        .map(|(resource_index, (rate, _))| (ResourceId(resource_index), *rate))
+        .collect();
+
+    let bars = resources
+        .iter()
+        .enumerate()
+        .map(|(index, &(resource, rate))| {
+            Bar::new(index as f64, time_base.to_display(rate))
+                .name(world.name_of_resource(resource))
+                .fill(rate_color(rate))
+                .width(0.8)
+        })
+        .collect();
+
+    let names: Vec<String> = resources
+        .iter()
+        .map(|&(resource, _)| world.name_of_resource(resource).to_string())
+        .collect();
+
+    let response = Plot::new("net_rate_chart")
+        .height(200.)
+        .allow_zoom(false)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .allow_boxed_zoom(false)
+        .include_y(0.)
+        .x_axis_formatter(move |mark, _, _| {
+            let index = mark.value.round();
+
+            if (mark.value - index).abs() > 1e-6 || index < 0. {
+                return String::new();
+            }
+
+            names.get(index as usize).cloned().unwrap_or_default()
+        })
+        .show(ui, |plot_ui| {
+            plot_ui.bar_chart(BarChart::new(bars));
+
+            if plot_ui.response().clicked() {
+                plot_ui.pointer_coordinate()
+            } else {
+                None
+            }
+        });
+
+    let index = response.inner?.x.round();
+
+    if index < 0. {
+        return None;
+    }
+
+    resources.get(index as usize).map(|&(resource, _)| resource)
+}
+
+/// a rule list keeping each consumed raw resource's net at or above its solved rate,
+/// so the consumption can be adjusted from there
+fn input_rule_list(world: &World, net_resources: &NetResources) -> RuleList {
+    let mut rule_list = RuleList::default();
+
+    for resource in world.raw_resources() {
+        let rate = net_resources.resources[resource.0].0;
+
+        if rate < 0. {
+            rule_list.rules.push(Rule {
+                variable: resource.variable_id(),
+                constraint: Constraint::Greater(rate),
+                soft: false,
+                priority: 0,
+            });
+        }
+    }
+
+    rule_list
+}
+
+/// whether two variables are the same, or a recipe and a resource it produces or consumes
+fn linked(world: &World, a: VariableId, b: VariableId) -> bool {
+    let touches = |RecipeId(recipe): RecipeId, resource: ResourceId| {
+        world.recipes[recipe]
+            .rates
+            .iter()
+            .any(|&(other, _)| other == resource)
+    };
+
+    match (a, b) {
+        (VariableId::Recipe(recipe), VariableId::Resource(resource))
+        | (VariableId::Resource(resource), VariableId::Recipe(recipe)) => touches(recipe, resource),
+        _ => a == b,
+    }
+}
+
+/// resources made in set proportions, added as a basket in a new rule list
+///
+/// the scale is the rate of one relative amount, so a basket of 2 plates and 1 rod at a scale of 10
+/// makes 20 plates and 10 rods
+struct BasketBuilder {
+    /// each element is a ui id, a resource and its amount relative to the others
+    resources: Vec<(u64, Option<ResourceId>, f64)>,
+    resource_id_incrementor: u64,
+    scale: f64,
+}
+
+impl BasketBuilder {
+    fn new() -> Self {
+        BasketBuilder {
+            resources: Vec::new(),
+            resource_id_incrementor: 0,
+            scale: 1.,
+        }
+    }
+
+    fn show(&mut self, world: &World, ui: &mut Ui) {
+        ui.label("New Basket:");
+
+        ui.horizontal(|ui| {
+            ui.label("scale");
+            ui.add(DragValue::new(&mut self.scale).speed(0.1));
+            ui.label(rate_unit(ui, world, None));
+
+            if ui.button("New Resource").clicked() {
+                self.resources
+                    .push((self.resource_id_incrementor, None, 1.));
+                self.resource_id_incrementor += 1;
+            }
+        });
+
+        let mut remove = None;
+
+        for (index, (ui_id, selected_resource, amount)) in self.resources.iter_mut().enumerate() {
+            ui.push_id(ui_id, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(DragValue::new(amount).speed(0.1));
+
+                    let selected_text = match selected_resource {
+                        Some(resource) => world.name_of_resource(*resource),
+                        None => "...",
+                    };
+
+                    ComboBox::from_id_source("Resource")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            resource_options(ui, world, selected_resource);
+                        });
+
+                    if ui.button("Delete").clicked() {
+                        remove = Some(index);
+                    }
+                });
+            });
+        }
+
+        if let Some(index) = remove {
+            self.resources.remove(index);
+        }
+    }
+
+    /// builds the basket from the entries with a resource, converting the scale from the time base to per minute
+    fn build(&self, time_base: TimeBase) -> Option<Basket> {
+        let resources: Vec<_> = self
+            .resources
+            .iter()
+            .filter_map(|&(_, resource, amount)| Some((resource?, amount)))
+            .collect();
+
+        if resources.is_empty() {
+            return None;
+        }
+
+        Some(Basket {
+            resources,
+            scale: time_base.from_display(self.scale),
         })
     }
 }
+
+/// how much of a resource a single machine running a recipe makes, none if the recipe doesn't make it
+fn output_per_machine(world: &World, recipe: RecipeId, resource: ResourceId) -> Option<f64> {
+    world.recipes[recipe.0]
+        .rates
+        .iter()
+        .find(|&&(other, rate)| other == resource && rate > 0.)
+        .map(|&(_, rate)| rate)
+}
+
+/// solves a problem the way the page does, lexicographically when there is more than one tier
+/// with the objective being the sum of the tiers, then pruning recipes below the threshold if it's above zero
+fn solve_with_settings(
+    problem: &Problem,
+    world: &World,
+    tiers: &[Vec<(VariableId, f64)>],
+    threshold: f64,
+) -> SolveResult {
+    if tiers.len() > 1 && threshold > 0. {
+        problem
+            .prune_marginal_lexicographic(world, tiers, threshold)
+            .map(|(factory, values)| (factory, values.iter().sum()))
+    } else if tiers.len() > 1 {
+        problem
+            .solve_lexicographic(world, tiers)
+            .map(|(factory, values)| (factory, values.iter().sum()))
+    } else if threshold > 0. {
+        problem.prune_marginal(world, threshold)
+    } else {
+        problem.solve(world)
+    }
+}
+
+/// converts a rule's rate into the time base, recipe rates are machine counts so they are left alone
+fn display_rule_rate(variable: VariableId, rate: f64, time_base: TimeBase) -> f64 {
+    match variable {
+        VariableId::Resource(_) => time_base.to_display(rate),
+        VariableId::Recipe(_) => rate,
+    }
+}
+
+/// a search field followed by the resources and recipes matching it, for use inside a combo box
+///
+/// with `producers_of` only the recipes producing that resource are listed
+fn variable_options(
+    ui: &mut Ui,
+    world: &World,
+    selected_variable: &mut Option<VariableId>,
+    producers_of: Option<ResourceId>,
+) {
+    let matches = search_field(ui);
+
+    let producers = producers_of.map(|resource| world.producers_of(resource));
+
+    if producers.is_none() {
+        ui.label(RichText::new("Resources").strong());
+        for (resource_id, resource) in world.resources.iter().enumerate() {
+            if !matches(&resource.name) {
+                continue;
+            }
+
+            ui.selectable_value(
+                selected_variable,
+                Some(VariableId::Resource(ResourceId(resource_id))),
+                &resource.name,
+            );
+        }
+    }
+
+    ui.label(RichText::new("Recipes").strong());
+    for (recipe_id, recipe) in world.recipes.iter().enumerate() {
+        if !matches(&recipe.name) {
+            continue;
+        }
+
+        if producers
+            .as_ref()
+            .is_some_and(|producers| !producers.contains(&RecipeId(recipe_id)))
+        {
+            continue;
+        }
+
+        ui.selectable_value(
+            selected_variable,
+            Some(VariableId::Recipe(RecipeId(recipe_id))),
+            world.recipe_label(RecipeId(recipe_id)),
+        )
+        .on_hover_ui(|ui| recipe_tooltip(ui, world, RecipeId(recipe_id)));
+    }
+}
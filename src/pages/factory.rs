@@ -1,11 +1,20 @@
-use eframe::egui::{RichText, ScrollArea, Vec2};
+use std::path::Path;
+
+use eframe::egui::{ComboBox, Key, RichText, ScrollArea, TextEdit, Ui, Vec2, Window};
 
 use crate::{
-    factory::{save_factory, Factory, NetResources},
+    factory::{save_factory, Factory, NetResources, ParseFactoryError, ProductionNode},
+    save::write_atomic,
     world::{ResourceId, World},
 };
 
-use super::Page;
+use super::{
+    copy_factory_text, export_sankey, factory_summary, format_number, format_rate, net_rate_color,
+    resource_swatch, save_feedback, shortcut_pressed, Page,
+};
+
+/// how far from zero the net of a resource made and used within a factory may be before it's reported
+const BALANCE_TOLERANCE: f64 = 0.001;
 
 pub struct EditFactoryPage {
     world: World,
@@ -13,27 +22,33 @@ pub struct EditFactoryPage {
     factory: Factory,
     resources: NetResources,
     save_path: String,
-
-    sub_factory: Factory,
-    sub_resources: NetResources,
+    /// whether the last save succeeded
+    save_feedback: String,
+    /// show each recipe's rates for a single machine instead of for all its machines
+    per_machine: bool,
+    /// the resource the production tree is built for
+    tree_root: Option<ResourceId>,
+    /// the text in the "Paste Factory" window, none while it's closed
+    paste_text: Option<String>,
+    /// why the pasted text couldn't be imported
+    paste_feedback: String,
 }
 
 impl EditFactoryPage {
     pub fn new(world: World, factory: Factory) -> Self {
         let resources = factory.net_resources(&world);
 
-        let sub_factory = Factory::default();
-        let sub_resources = sub_factory.net_resources(&world);
-
         EditFactoryPage {
             world,
 
             factory,
             resources,
             save_path: String::new(),
-
-            sub_factory,
-            sub_resources,
+            save_feedback: String::new(),
+            per_machine: false,
+            tree_root: None,
+            paste_text: None,
+            paste_feedback: String::new(),
         }
     }
 }
@@ -42,15 +57,129 @@ impl Page for EditFactoryPage {
     fn show(mut self: Box<Self>, ui: &mut eframe::egui::Ui) -> Box<dyn Page> {
         ui.heading("Edit Factory");
 
-        let save = ui.button("Save").clicked();
-        ui.text_edit_singleline(&mut self.save_path);
+        let (save, export, paste, reset) = ui
+            .horizontal(|ui| {
+                (
+                    ui.button("Save").on_hover_text("Ctrl+S").clicked()
+                        || shortcut_pressed(ui, Key::S),
+                    ui.button("Export Lines")
+                        .on_hover_text(
+                            "Saves a .jsonl file next to the path with a recipe per line",
+                        )
+                        .clicked(),
+                    ui.button("Paste Factory")
+                        .on_hover_text(
+                            "Import lines like \"4x Recipe\", as copied with \"Copy as Text\"",
+                        )
+                        .clicked(),
+                    ui.button("Reset")
+                        .on_hover_text("Clear the save path and view options, the factory is kept")
+                        .clicked(),
+                )
+            })
+            .inner;
+
+        if reset {
+            return Box::new(EditFactoryPage::new(self.world, self.factory));
+        }
+
+        if paste {
+            self.paste_text.get_or_insert_with(String::new);
+        }
+
+        if let Some(text) = &mut self.paste_text {
+            let mut open = true;
+            let mut import = false;
+
+            Window::new("Paste Factory")
+                .open(&mut open)
+                .vscroll(true)
+                .show(ui.ctx(), |ui| {
+                    ui.add(
+                        TextEdit::multiline(text)
+                            .hint_text("4x Iron Plate")
+                            .desired_rows(10),
+                    );
+
+                    import = ui.button("Import").clicked();
+                    ui.label(&self.paste_feedback);
+                });
+
+            if import {
+                match Factory::from_text(&self.world, text) {
+                    Ok(factory) => return Box::new(EditFactoryPage::new(self.world, factory)),
+                    Err(errors) => {
+                        let lines: Vec<_> = errors
+                            .into_iter()
+                            .map(|err| match err {
+                                ParseFactoryError::BadLine { line } => {
+                                    format!("Line {}: expected a line like \"4x Recipe\"", line)
+                                }
+                                ParseFactoryError::BadRecipeName { line, recipe_name } => {
+                                    format!("Line {}: unknown recipe \"{}\"", line, recipe_name)
+                                }
+                            })
+                            .collect();
+
+                        self.paste_feedback = lines.join("\n");
+                    }
+                }
+            }
+
+            if !open {
+                self.paste_text = None;
+                self.paste_feedback.clear();
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.save_path);
+            ui.label(&self.save_feedback);
+        });
 
         if save {
-            save_factory(&self.world, &self.factory, &self.save_path);
+            self.save_feedback =
+                save_feedback(save_factory(&self.world, &self.factory, &self.save_path));
+        }
+
+        if export {
+            let mut lines = Vec::new();
+
+            self.factory
+                .write_jsonl(&self.world, &mut lines)
+                .expect("Failed to write to a vec");
+
+            self.save_feedback = save_feedback(write_atomic(
+                Path::new(&self.save_path).with_extension("jsonl"),
+                &lines,
+            ));
+        }
+
+        let unbalanced = self.resources.unbalanced(BALANCE_TOLERANCE);
+
+        if !unbalanced.is_empty() {
+            let names: Vec<_> = unbalanced
+                .iter()
+                .map(|&(resource, rate)| {
+                    format!(
+                        "{} {}",
+                        self.world.name_of_resource(resource),
+                        format_rate(ui, &self.world, resource, rate)
+                    )
+                })
+                .collect();
+
+            ui.label(
+                RichText::new(format!("Unbalanced: {}", names.join(", ")))
+                    .color(ui.visuals().warn_fg_color),
+            )
+            .on_hover_text(
+                "These resources are made and used in the factory but don't net to zero, \
+                the factory may not match the world's recipes",
+            );
         }
 
         let available_space = ui.available_rect_before_wrap();
-        let collumn_width = available_space.width() / 2.;
+        let collumn_width = available_space.width() / 3.;
 
         ui.push_id("Factory", |ui| {
             let mut collumn = available_space;
@@ -71,29 +200,69 @@ impl Page for EditFactoryPage {
                             let resource_name =
                                 self.world.name_of_resource(ResourceId(resource_index));
 
-                            ui.label(
-                                RichText::new(format!(
-                                    "{} net {:.0000001} /min",
-                                    resource_name, rate
-                                ))
-                                .strong(),
-                            );
+                            ui.horizontal(|ui| {
+                                resource_swatch(ui, &self.world, ResourceId(resource_index));
+                                ui.label(
+                                    net_rate_color(
+                                        format!(
+                                            "{} net {}",
+                                            resource_name,
+                                            format_rate(
+                                                ui,
+                                                &self.world,
+                                                ResourceId(resource_index),
+                                                *rate
+                                            )
+                                        ),
+                                        *rate,
+                                    )
+                                    .strong(),
+                                );
+
+                                if let Some(usage) =
+                                    self.world.node_usage(ResourceId(resource_index), *rate)
+                                {
+                                    ui.label(usage);
+                                }
+                            });
 
-                            for &(recipe, rate) in recipes.iter() {
+                            for (recipe, rate) in self
+                                .resources
+                                .sorted_contributors(&self.world, ResourceId(resource_index))
+                            {
                                 let recipe_name = self.world.name_of_recipe(recipe);
 
-                                ui.label(format!("  {} {:.0000001} /min", recipe_name, rate));
+                                let mut text = format!(
+                                    "  {} {}",
+                                    recipe_name,
+                                    format_rate(ui, &self.world, ResourceId(resource_index), rate)
+                                );
+
+                                if let Some(warning) = self
+                                    .world
+                                    .throughput_warning(ResourceId(resource_index), rate)
+                                {
+                                    text.push_str(&format!(" ({})", warning));
+                                }
+
+                                ui.label(text);
                             }
                         }
                     });
 
+                    factory_summary(ui, &self.world, &self.factory);
+                    copy_factory_text(ui, &self.world, &self.factory);
+                    export_sankey(ui, &self.world, &self.factory);
+
                     ui.collapsing("Recipes", |ui| {
+                        ui.checkbox(&mut self.per_machine, "Normalize to 1 machine");
+
                         for &(recipe, rate) in self.factory.recipes.iter() {
-                            let recipe_name = self.world.name_of_recipe(recipe);
+                            let recipe_name = self.world.recipe_label(recipe);
 
                             ui.horizontal(|ui| {
                                 ui.label(RichText::new(recipe_name).strong());
-                                ui.label(format!("{:.0000001} machines", rate));
+                                ui.label(format!("{} machines", format_number(ui, rate)));
                             });
 
                             for &(resource, resource_rate) in
@@ -101,10 +270,16 @@ impl Page for EditFactoryPage {
                             {
                                 let resource_name = self.world.name_of_resource(resource);
 
+                                let shown_rate = if self.per_machine {
+                                    resource_rate
+                                } else {
+                                    rate * resource_rate
+                                };
+
                                 ui.label(format!(
-                                    "  {} {:.0000001} /min",
+                                    "  {} {}",
                                     resource_name,
-                                    rate * resource_rate
+                                    format_rate(ui, &self.world, resource, shown_rate)
                                 ));
                             }
                         }
@@ -113,10 +288,47 @@ impl Page for EditFactoryPage {
             });
         });
 
-        ui.push_id("Sub Factory", |ui| {
+        ui.push_id("Production Tree", |ui| {
             let mut collumn = available_space.translate(Vec2::new(collumn_width, 0.));
             collumn.set_width(collumn_width);
 
+            ui.allocate_ui_at_rect(collumn, |ui| {
+                ui.heading("Production Tree");
+
+                let selected_text = match self.tree_root {
+                    Some(resource) => self.world.name_of_resource(resource),
+                    None => "...",
+                };
+
+                ComboBox::from_label("Output")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        // only resources the factory makes have a tree
+                        for (index, (rate, _)) in self.resources.resources.iter().enumerate() {
+                            if *rate > 0. {
+                                ui.selectable_value(
+                                    &mut self.tree_root,
+                                    Some(ResourceId(index)),
+                                    self.world.name_of_resource(ResourceId(index)),
+                                );
+                            }
+                        }
+                    });
+
+                if let Some(root) = self.tree_root {
+                    let tree = self.factory.production_tree(&self.world, root);
+
+                    ScrollArea::new([false, true]).show(ui, |ui| {
+                        production_tree(ui, &self.world, &tree);
+                    });
+                }
+            });
+        });
+
+        ui.push_id("Sub Factory", |ui| {
+            let mut collumn = available_space.translate(Vec2::new(collumn_width * 2., 0.));
+            collumn.set_width(collumn_width);
+
             ui.allocate_ui_at_rect(collumn, |ui| {
                 ui.heading("Sub Factory");
 
@@ -127,3 +339,35 @@ impl Page for EditFactoryPage {
         self
     }
 }
+
+/// shows a production tree as nested collapsing sections
+fn production_tree(ui: &mut Ui, world: &World, node: &ProductionNode) {
+    let text = format!(
+        "{} {}",
+        world.name_of_resource(node.resource),
+        format_rate(ui, world, node.resource, node.rate)
+    );
+
+    if node.producers.is_empty() {
+        ui.label(text);
+        return;
+    }
+
+    ui.collapsing(text, |ui| {
+        for producer in node.producers.iter() {
+            let text = format!(
+                "{} {} machines",
+                world.name_of_recipe(producer.recipe),
+                format_number(ui, producer.machines)
+            );
+
+            ui.push_id(producer.recipe.0, |ui| {
+                ui.collapsing(RichText::new(text).strong(), |ui| {
+                    for input in producer.inputs.iter() {
+                        ui.push_id(input.resource.0, |ui| production_tree(ui, world, input));
+                    }
+                });
+            });
+        }
+    });
+}
@@ -1,7 +1,17 @@
-use eframe::egui::Ui;
+use std::time::SystemTime;
+
+use eframe::egui::{Color32, Context, CursorIcon, Id, Key, Rect, RichText, Sense, Ui};
+
+use crate::{
+    config::{TimeBase, DEFAULT_PRECISION},
+    factory::Factory,
+    save::{modified_time, SaveError},
+    world::{LoadWorldError, RecipeId, ResourceId, World},
+};
 
 pub mod builder;
 pub mod factory;
+pub mod project;
 pub mod world;
 
 pub trait Page {
@@ -15,3 +25,436 @@ impl Page for DefaultPage {
         panic!("Default page was reached");
     }
 }
+
+/// whether ctrl (or cmd on mac) and a key were pressed this frame, without shift
+///
+/// ignored while a text field has focus so typing can't trigger a shortcut
+pub fn shortcut_pressed(ui: &Ui, key: Key) -> bool {
+    !ui.ctx().wants_keyboard_input()
+        && ui.input(|input| {
+            input.modifiers.command && !input.modifiers.shift && input.key_pressed(key)
+        })
+}
+
+/// whether ctrl (or cmd on mac), shift and a key were pressed this frame, see [shortcut_pressed]
+pub fn shift_shortcut_pressed(ui: &Ui, key: Key) -> bool {
+    !ui.ctx().wants_keyboard_input()
+        && ui.input(|input| {
+            input.modifiers.command && input.modifiers.shift && input.key_pressed(key)
+        })
+}
+
+/// parses a number typed by the user, rejecting infinities and NaN which would make a meaningless problem
+pub fn parse_number(text: &str) -> Option<f64> {
+    text.parse::<f64>().ok().filter(|number| number.is_finite())
+}
+
+/// a status message for the result of saving a file
+pub fn save_feedback(result: Result<(), SaveError>) -> String {
+    match result {
+        Ok(()) => "Saved".into(),
+        Err(SaveError::IoError(err)) => format!("Failed to save: {}", err),
+    }
+}
+
+/// describes a json error with where it happened, so typos in large files can be found
+pub fn json_error_message(err: &serde_json::Error) -> String {
+    format!(
+        "Invalid Json at line {}, column {}",
+        err.line(),
+        err.column()
+    )
+}
+
+/// describes why a world file couldn't be loaded
+pub fn world_error_message(err: LoadWorldError) -> String {
+    match err {
+        LoadWorldError::IoError(_) => "Io Error".into(),
+        LoadWorldError::JsonError(err) => json_error_message(&err),
+        LoadWorldError::BadRecipeResource {
+            recipe_name,
+            resource_name,
+        } => format!(
+            "Bad resource name \"{}\" in recipe \"{}\"",
+            resource_name, recipe_name
+        ),
+        LoadWorldError::BadPowerResource { resource_name } => {
+            format!("Bad power resource name \"{}\"", resource_name)
+        }
+        LoadWorldError::BadNodeResource { resource_name } => {
+            format!("Bad node resource name \"{}\"", resource_name)
+        }
+        LoadWorldError::BadPerMinute {
+            recipe_name,
+            per_minute,
+        } => format!(
+            "Recipe \"{}\" has per_minute {}, it must be positive",
+            recipe_name, per_minute
+        ),
+        LoadWorldError::BadRecipeBase {
+            recipe_name,
+            base_name,
+        } => format!(
+            "Recipe \"{}\" extends unknown recipe \"{}\"",
+            recipe_name, base_name
+        ),
+        LoadWorldError::RecipeCycle { recipe_name } => {
+            format!("Recipe \"{}\" extends itself", recipe_name)
+        }
+        LoadWorldError::BadGameDocs { reason } => format!("Bad game docs, {}", reason),
+    }
+}
+
+/// a "Reload World" button, warning when the world file changed on disk since `loaded_modified`,
+/// returns whether the button was clicked
+pub fn world_reload_row(
+    ui: &mut Ui,
+    world_path: &str,
+    loaded_modified: Option<SystemTime>,
+    feedback: &str,
+) -> bool {
+    ui.horizontal(|ui| {
+        let reload = ui
+            .button("Reload World")
+            .on_hover_text(format!("Read \"{}\" again", world_path))
+            .clicked();
+
+        if modified_time(world_path) != loaded_modified {
+            ui.label(
+                RichText::new("The world file changed on disk").color(ui.visuals().warn_fg_color),
+            );
+        }
+
+        ui.label(feedback);
+
+        reload
+    })
+    .inner
+}
+
+/// makes the app wide time base available to pages
+pub fn set_time_base(ctx: &Context, time_base: TimeBase) {
+    ctx.data_mut(|data| data.insert_temp(Id::new("time_base"), time_base));
+}
+
+/// the time base rates should be displayed and entered in
+pub fn time_base(ui: &Ui) -> TimeBase {
+    ui.data(|data| data.get_temp(Id::new("time_base")))
+        .unwrap_or_default()
+}
+
+/// makes the app wide display precision available to pages
+pub fn set_precision(ctx: &Context, precision: usize) {
+    ctx.data_mut(|data| data.insert_temp(Id::new("precision"), precision));
+}
+
+/// how many decimals machine counts and rates should be displayed with
+pub fn precision(ui: &Ui) -> usize {
+    ui.data(|data| data.get_temp(Id::new("precision")))
+        .unwrap_or(DEFAULT_PRECISION)
+}
+
+/// makes the app wide choice of thousands separators available to pages
+pub fn set_thousands_separator(ctx: &Context, thousands_separator: bool) {
+    ctx.data_mut(|data| data.insert_temp(Id::new("thousands_separator"), thousands_separator));
+}
+
+/// whether numbers should be displayed with thousands separators
+pub fn thousands_separator(ui: &Ui) -> bool {
+    ui.data(|data| data.get_temp(Id::new("thousands_separator")))
+        .unwrap_or(true)
+}
+
+/// where the first and second of three equal columns end, as fractions of the width
+pub const DEFAULT_COLUMN_SPLITS: [f32; 2] = [1. / 3., 2. / 3.];
+
+/// the narrowest a column can be dragged to, as a fraction of the width
+const MIN_COLUMN_FRACTION: f32 = 0.1;
+
+/// splits the space left in a ui into three columns, `splits` being the fractions of the width where
+/// the first and second columns end
+///
+/// the boundaries between the columns can be dragged to resize them, which updates `splits`
+pub fn columns(ui: &mut Ui, splits: &mut [f32; 2]) -> [Rect; 3] {
+    let available_space = ui.available_rect_before_wrap();
+    let width = available_space.width();
+
+    for (index, split) in splits.iter_mut().enumerate() {
+        let x = available_space.left() + *split * width;
+        let handle = Rect::from_x_y_ranges(x - 3.0..=x + 3.0, available_space.y_range());
+
+        let response = ui
+            .interact(handle, ui.id().with(("Column Split", index)), Sense::drag())
+            .on_hover_cursor(CursorIcon::ResizeHorizontal);
+
+        if response.dragged() {
+            *split += response.drag_delta().x / width;
+        }
+
+        let stroke = if response.hovered() || response.dragged() {
+            ui.visuals().widgets.hovered.fg_stroke
+        } else {
+            ui.visuals().widgets.noninteractive.bg_stroke
+        };
+
+        ui.painter().vline(x, available_space.y_range(), stroke);
+    }
+
+    splits[0] = splits[0].clamp(MIN_COLUMN_FRACTION, 1. - 2. * MIN_COLUMN_FRACTION);
+    splits[1] = splits[1].clamp(splits[0] + MIN_COLUMN_FRACTION, 1. - MIN_COLUMN_FRACTION);
+
+    let edges = [
+        available_space.left(),
+        available_space.left() + splits[0] * width,
+        available_space.left() + splits[1] * width,
+        available_space.right(),
+    ];
+
+    [0, 1, 2].map(|index| {
+        Rect::from_x_y_ranges(edges[index]..=edges[index + 1], available_space.y_range())
+    })
+}
+
+/// formats a number with the current precision
+pub fn format_number(ui: &Ui, number: f64) -> String {
+    with_separators(ui, format!("{:.*}", precision(ui), number))
+}
+
+/// adds thousands separators to an already formatted number if they're turned on
+pub fn with_separators(ui: &Ui, number: String) -> String {
+    if thousands_separator(ui) {
+        group_thousands(&number)
+    } else {
+        number
+    }
+}
+
+/// puts a comma between every three digits before the decimal point, like "-9,600.5"
+fn group_thousands(number: &str) -> String {
+    let digits_start = number.find(|c: char| c.is_ascii_digit()).unwrap_or(0);
+    let digits_end = number[digits_start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(number.len(), |end| digits_start + end);
+
+    let mut grouped = number[..digits_start].to_string();
+
+    for (index, digit) in number[digits_start..digits_end].chars().enumerate() {
+        if index > 0 && (digits_end - digits_start - index) % 3 == 0 {
+            grouped.push(',');
+        }
+
+        grouped.push(digit);
+    }
+
+    grouped.push_str(&number[digits_end..]);
+    grouped
+}
+
+/// formats a per minute rate of a resource in the current time base, like "2.5 /sec" or "120 m³/min"
+pub fn format_rate(ui: &Ui, world: &World, resource: ResourceId, per_minute: f64) -> String {
+    format!(
+        "{} {}",
+        format_number(ui, time_base(ui).to_display(per_minute)),
+        rate_unit(ui, world, Some(resource))
+    )
+}
+
+/// the unit a resource's rates are shown in, like "m³/min", just the time base when there's no resource
+pub fn rate_unit(ui: &Ui, world: &World, resource: Option<ResourceId>) -> String {
+    let unit = resource.map_or("", |resource| world.unit_of(resource));
+
+    format!("{}{}", unit, time_base(ui).suffix())
+}
+
+/// a small square in a resource's color, if it has one
+pub fn resource_swatch(ui: &mut Ui, world: &World, resource: ResourceId) {
+    if let Some([r, g, b]) = world.resources[resource.0].color {
+        ui.label(RichText::new("■").color(Color32::from_rgb(r, g, b)));
+    }
+}
+
+/// colors text by the sign of a net rate, green for a surplus, red for a deficit and gray when balanced
+pub fn net_rate_color(text: impl Into<String>, rate: f64) -> RichText {
+    RichText::new(text).color(rate_color(rate))
+}
+
+/// green for a surplus, red for a deficit and gray for balanced
+pub fn rate_color(rate: f64) -> Color32 {
+    if rate.abs() < 1e-6 {
+        Color32::GRAY
+    } else if rate > 0. {
+        Color32::GREEN
+    } else {
+        Color32::RED
+    }
+}
+
+/// a one line summary of a factory's size, like "Total: 47 machines, 3200 m²"
+pub fn factory_summary(ui: &mut Ui, world: &World, factory: &Factory) {
+    let mut text = format!(
+        "Total: {} machines",
+        with_separators(ui, factory.total_machines(true).to_string())
+    );
+
+    if let Some(footprint) = factory.total_footprint(world) {
+        text.push_str(&format!(
+            ", {} m²",
+            with_separators(ui, footprint.to_string())
+        ));
+    }
+
+    let exact = format_number(ui, factory.total_machines(false));
+    ui.label(text)
+        .on_hover_text(format!("{} machines running", exact));
+}
+
+/// a button copying a plain text list of the factory's machines and its net inputs and outputs, for sharing builds
+pub fn copy_factory_text(ui: &mut Ui, world: &World, factory: &Factory) {
+    if !ui.button("Copy as Text").clicked() {
+        return;
+    }
+
+    let mut text = factory.machine_list(world);
+
+    for (heading, inputs) in [("Inputs", true), ("Outputs", false)] {
+        text.push_str(&format!("\n{}:\n", heading));
+
+        for (resource_index, (rate, recipes)) in
+            factory.net_resources(world).resources.iter().enumerate()
+        {
+            if recipes.is_empty() || rate.abs() < 1e-6 || (*rate < 0.) != inputs {
+                continue;
+            }
+
+            text.push_str(&format!(
+                "  {} {}\n",
+                world.name_of_resource(ResourceId(resource_index)),
+                format_rate(ui, world, ResourceId(resource_index), rate.abs())
+            ));
+        }
+    }
+
+    ui.output_mut(|output| output.copied_text = text);
+}
+
+/// a button copying the factory's flows for pasting into SankeyMatic to draw a flow diagram
+pub fn export_sankey(ui: &mut Ui, world: &World, factory: &Factory) {
+    if ui
+        .button("Export Sankey")
+        .on_hover_text("Copies the flows between recipes and resources in SankeyMatic's format")
+        .clicked()
+    {
+        let sankey = factory.to_sankey(world);
+        ui.output_mut(|output| output.copied_text = sankey);
+    }
+}
+
+/// a checkbox for each recipe, grouped by building with buttons to check or uncheck a whole group
+///
+/// returns whether any checkbox changed
+pub fn recipe_checkboxes(
+    ui: &mut Ui,
+    world: &World,
+    buildings: &[(String, Vec<RecipeId>)],
+    checked: &mut [bool],
+) -> bool {
+    let mut changed = false;
+
+    for (building, recipes) in buildings.iter() {
+        ui.collapsing(building, |ui| {
+            ui.horizontal(|ui| {
+                for (text, value) in [("Add all", true), ("Remove all", false)] {
+                    if ui.button(text).clicked() {
+                        for &RecipeId(index) in recipes.iter() {
+                            checked[index] = value;
+                        }
+
+                        changed = true;
+                    }
+                }
+            });
+
+            for &recipe in recipes.iter() {
+                changed |= ui
+                    .checkbox(&mut checked[recipe.0], world.recipe_label(recipe))
+                    .on_hover_ui(|ui| recipe_tooltip(ui, world, recipe))
+                    .changed();
+            }
+        });
+    }
+
+    changed
+}
+
+/// the contents of a tooltip describing a recipe's note and its inputs and outputs
+pub fn recipe_tooltip(ui: &mut Ui, world: &World, recipe: RecipeId) {
+    let recipe = &world.recipes[recipe.0];
+
+    if let Some(note) = &recipe.note {
+        ui.label(note);
+    }
+
+    for (heading, inputs) in [("In", true), ("Out", false)] {
+        ui.label(RichText::new(heading).strong());
+
+        for &(resource, rate) in recipe.rates.iter() {
+            if (rate < 0.) == inputs {
+                ui.label(format!(
+                    "  {} {}",
+                    world.name_of_resource(resource),
+                    format_rate(ui, world, resource, rate.abs())
+                ));
+            }
+        }
+    }
+}
+
+/// a search field for use inside a combo box, returns a function matching names against the search
+///
+/// the search text is kept in egui's memory so it persists while the combo box is open
+pub fn search_field(ui: &mut Ui) -> impl Fn(&str) -> bool {
+    let search_id = ui.id().with("Search");
+    let mut search = ui
+        .data_mut(|data| data.get_temp::<String>(search_id))
+        .unwrap_or_default();
+
+    ui.text_edit_singleline(&mut search);
+
+    let filter = search.to_lowercase();
+    ui.data_mut(|data| data.insert_temp(search_id, search));
+
+    move |name: &str| name.to_lowercase().contains(&filter)
+}
+
+/// a search field followed by the resources matching it, for use inside a combo box
+pub fn resource_options(ui: &mut Ui, world: &World, selected_resource: &mut Option<ResourceId>) {
+    let matches = search_field(ui);
+
+    for (resource_id, resource) in world.resources.iter().enumerate() {
+        if matches(&resource.name) {
+            ui.selectable_value(
+                selected_resource,
+                Some(ResourceId(resource_id)),
+                &resource.name,
+            );
+        }
+    }
+}
+
+/// a search field followed by the recipes matching it, for use inside a combo box
+pub fn recipe_options(ui: &mut Ui, world: &World, selected_recipe: &mut Option<RecipeId>) {
+    let matches = search_field(ui);
+
+    for (recipe_id, recipe) in world.recipes.iter().enumerate() {
+        if !matches(&recipe.name) {
+            continue;
+        }
+
+        ui.selectable_value(
+            selected_recipe,
+            Some(RecipeId(recipe_id)),
+            world.recipe_label(RecipeId(recipe_id)),
+        )
+        .on_hover_ui(|ui| recipe_tooltip(ui, world, RecipeId(recipe_id)));
+    }
+}
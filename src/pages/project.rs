@@ -0,0 +1,132 @@
+use eframe::egui::{ScrollArea, Ui};
+
+use crate::{
+    factory::{load_factory, Factory, LoadFactoryError, NetResources},
+    world::{ResourceId, World},
+};
+
+use super::{format_rate, json_error_message, net_rate_color, resource_swatch, Page};
+
+/// several saved factories loaded against one world, with their combined net resources
+pub struct ProjectPage {
+    world: World,
+    /// the recipe selection page, returned to by "Back"
+    back: Box<dyn Page>,
+
+    /// each element is the path a factory was loaded from and the factory
+    factories: Vec<(String, Factory)>,
+    /// the net resources of all the factories together
+    combined: NetResources,
+    path_field: String,
+    /// why the factory at `path_field` couldn't be loaded
+    feedback: String,
+}
+
+impl ProjectPage {
+    pub fn new(world: World, back: Box<dyn Page>) -> Self {
+        let combined = NetResources::combine(&[]);
+
+        ProjectPage {
+            world,
+            back,
+
+            factories: Vec::new(),
+            combined,
+            path_field: String::new(),
+            feedback: String::new(),
+        }
+    }
+
+    fn update_combined(&mut self) {
+        let net_resources: Vec<_> = self
+            .factories
+            .iter()
+            .map(|(_, factory)| factory.net_resources(&self.world))
+            .collect();
+
+        self.combined = NetResources::combine(&net_resources);
+    }
+}
+
+impl Page for ProjectPage {
+    fn show(mut self: Box<Self>, ui: &mut Ui) -> Box<dyn Page> {
+        ui.heading("Project");
+
+        if ui.button("Back").clicked() {
+            return self.back;
+        }
+
+        let add = ui
+            .horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.path_field);
+                let add = ui.button("Add Factory").clicked();
+                ui.label(&self.feedback);
+                add
+            })
+            .inner;
+
+        if add {
+            self.feedback.clear();
+
+            match load_factory(&self.world, &self.path_field) {
+                Ok(factory) => {
+                    self.factories.push((self.path_field.clone(), factory));
+                    self.update_combined();
+                }
+                Err(LoadFactoryError::IoError(_)) => self.feedback = "Io Error".into(),
+                Err(LoadFactoryError::JsonError(err)) => self.feedback = json_error_message(&err),
+                Err(LoadFactoryError::BadRecipeName { recipe_name }) => {
+                    self.feedback = format!("Bad recipe name \"{}\"", recipe_name)
+                }
+            }
+        }
+
+        let mut remove = None;
+
+        for (index, (path, factory)) in self.factories.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button("Remove").clicked() {
+                    remove = Some(index);
+                }
+
+                ui.label(format!("{} ({} recipes)", path, factory.recipes.len()));
+            });
+        }
+
+        if let Some(index) = remove {
+            self.factories.remove(index);
+            self.update_combined();
+        }
+
+        ui.separator();
+        ui.heading("Net Resources");
+
+        ScrollArea::new([false, true]).show(ui, |ui| {
+            for (resource_index, (rate, contributors)) in self.combined.resources.iter().enumerate()
+            {
+                if contributors.is_empty() {
+                    continue;
+                }
+
+                let resource = ResourceId(resource_index);
+
+                ui.horizontal(|ui| {
+                    resource_swatch(ui, &self.world, resource);
+                    ui.label(
+                        net_rate_color(
+                            format!(
+                                "{} net {}",
+                                self.world.name_of_resource(resource),
+                                format_rate(ui, &self.world, resource, *rate)
+                            ),
+                            *rate,
+                        )
+                        .strong(),
+                    );
+                });
+            }
+        });
+
+        self
+    }
+}
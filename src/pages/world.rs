@@ -1,16 +1,28 @@
-use eframe::egui::{Key, ScrollArea, Ui, Vec2};
+use std::time::SystemTime;
+
+use eframe::egui::{
+    Button, CollapsingHeader, ComboBox, Grid, Key, RichText, ScrollArea, Slider, Ui, Vec2, Window,
+};
 
 use crate::{
-    factory::load_factory,
-    world::{load_world, LoadWorldError, World},
+    config::{load_config, update_config},
+    factory::{load_factory, remap_factory, LoadFactoryError},
+    save::modified_time,
+    world::{load_world_from, RecipeId, ResourceId, World},
 };
 
-use super::{builder::BuildFactoryPage, factory::EditFactoryPage, Page};
+use super::{
+    builder::BuildFactoryPage, columns, factory::EditFactoryPage, format_number,
+    json_error_message, project::ProjectPage, recipe_checkboxes, recipe_options, recipe_tooltip,
+    resource_swatch, search_field, world_error_message, world_reload_row, Page,
+    DEFAULT_COLUMN_SPLITS,
+};
 
 pub struct LandingPage {
     input: String,
     valid_path: bool,
     feedback: std::borrow::Cow<'static, str>,
+    recent_worlds: Vec<String>,
 }
 
 impl LandingPage {
@@ -19,7 +31,31 @@ impl LandingPage {
             input: String::new(),
             valid_path: false,
             feedback: "Input the path to the world json configuration.".into(),
+            recent_worlds: load_config().recent_worlds,
+        }
+    }
+
+    /// loads the world at the input path, returning the next page if successful
+    ///
+    /// if `game_docs` the path is the game's docs file rather than a world file
+    fn load(&mut self, game_docs: bool) -> Option<Box<dyn Page>> {
+        let path = std::path::Path::new(&self.input);
+
+        match load_world_from(path, game_docs) {
+            Ok(world) => {
+                let path = self.input.clone();
+
+                // recent worlds are reopened as world files
+                if !game_docs {
+                    update_config(|config| config.add_recent_world(path.clone()));
+                }
+
+                return Some(Box::new(LoadedPage::new(world, path)));
+            }
+            Err(err) => self.feedback = world_error_message(err).into(),
         }
+
+        None
     }
 }
 
@@ -51,83 +87,151 @@ impl Page for LandingPage {
                     && text_box.lost_focus()
                     && text_box.ctx.input(|input| input.key_pressed(Key::Enter)))
             {
-                let path = std::path::Path::new(&self.input);
-                match load_world(path) {
-                    Ok(world) => {
-                        next_page = Some(Box::new(LoadedPage::new(world)));
-                    }
-                    Err(LoadWorldError::IoError(_)) => {
-                        self.feedback = "Io Error".into();
-                    }
-                    Err(LoadWorldError::JsonError(_)) => {
-                        self.feedback = "Invalid Json".into();
-                    }
-                    Err(LoadWorldError::BadRecipeResource {
-                        recipe_name,
-                        resource_name,
-                    }) => {
-                        self.feedback = format!(
-                            "Bad resource name \"{}\" in recipe \"{}\"",
-                            resource_name, recipe_name
-                        )
-                        .into();
-                    }
-                }
+                next_page = self.load(false);
+            }
+
+            if ui
+                .button("Import Game Docs")
+                .on_hover_text(
+                    "Build a world from the Docs.json in the game's CommunityResources folder",
+                )
+                .clicked()
+            {
+                next_page = self.load(true);
             }
         });
 
         ui.label(self.feedback.as_ref());
 
+        if !self.recent_worlds.is_empty() {
+            ui.label("Recent worlds:");
+
+            let mut open = None;
+
+            for path in self.recent_worlds.iter() {
+                if ui.button(path).clicked() {
+                    open = Some(path.clone());
+                }
+            }
+
+            if let Some(path) = open {
+                self.input = path;
+                self.valid_path = true;
+                next_page = self.load(false);
+            }
+        }
+
         next_page.unwrap_or(self)
     }
 }
 
 struct LoadedPage {
     world: World,
+    world_path: String,
     tags: Vec<String>,
+    /// recipe indices grouped by building, with recipes that have no building last under "Other"
+    buildings: Vec<(String, Vec<RecipeId>)>,
     included: Vec<bool>,
+    /// the highest tier recipes may unlock at, none if the world has no tiers
+    max_tier: Option<u32>,
+    /// pairs of recipes that undo each other, warned about while both are included
+    inverse_recipes: Vec<(RecipeId, RecipeId)>,
     open_field: String,
+    /// why the factory at `open_field` couldn't be opened
+    open_feedback: String,
+    /// a recipe name the factory at `open_field` uses that isn't in the world, and the recipe to map it to
+    open_remap: Option<(String, Option<RecipeId>)>,
+    /// where the resources and tags columns end, as fractions of the width
+    column_splits: [f32; 2],
+    /// when the world file was last modified as of loading it, to notice edits made on disk
+    world_modified: Option<SystemTime>,
+    /// the result of the last world reload
+    reload_feedback: String,
 }
 
 impl LoadedPage {
-    fn new(world: World) -> Self {
-        let mut tags = Vec::new();
+    fn new(world: World, world_path: String) -> Self {
+        let tags = world.tags();
 
-        for recipe in world.recipes.iter() {
-            for tag in recipe.tags.iter() {
-                if !tags.contains(tag) {
-                    tags.push(tag.clone());
-                }
-            }
-        }
+        let buildings = world.recipes_by_building();
 
         let included = vec![true; world.recipes.len()];
 
+        let max_tier = world.max_tier();
+
+        let inverse_recipes = world.detect_recipe_cycles_zero_cost();
+
+        let world_modified = modified_time(&world_path);
+
         LoadedPage {
             world,
+            world_path,
             tags,
+            buildings,
             included,
+            max_tier,
+            inverse_recipes,
             open_field: String::new(),
+            open_feedback: String::new(),
+            open_remap: None,
+            column_splits: DEFAULT_COLUMN_SPLITS,
+            world_modified,
+            reload_feedback: String::new(),
         }
     }
 
-    fn filter_world(self) -> World {
-        World {
-            recipes: self
-                .world
-                .recipes
-                .into_iter()
-                .enumerate()
-                .filter_map(|(index, recipe)| {
-                    if self.included[index] {
-                        Some(recipe)
-                    } else {
-                        None
-                    }
-                })
-                .collect(),
-            ..self.world
+    /// reads the world file again, keeping the recipe choices by name
+    ///
+    /// recipes new to the file start included, and recipes that were removed from it are reported
+    fn reload(mut self: Box<Self>) -> Box<dyn Page> {
+        let world = match load_world_from(&self.world_path, self.world.from_game_docs) {
+            Ok(world) => world,
+            Err(err) => {
+                self.reload_feedback = world_error_message(err);
+                return self;
+            }
+        };
+
+        let mut page = LoadedPage::new(world, self.world_path);
+
+        for (included, recipe) in page.included.iter_mut().zip(page.world.recipes.iter()) {
+            if let Some(RecipeId(index)) = self.world.recipe_id_of_name(&recipe.name) {
+                *included = self.included[index];
+            }
         }
+
+        page.max_tier = page
+            .max_tier
+            .map(|highest_tier| self.max_tier.unwrap_or(highest_tier).min(highest_tier));
+        page.open_field = self.open_field;
+        page.column_splits = self.column_splits;
+
+        let removed: Vec<_> = self
+            .world
+            .recipes
+            .iter()
+            .filter(|recipe| page.world.recipe_id_of_name(&recipe.name).is_none())
+            .map(|recipe| recipe.name.as_str())
+            .collect();
+
+        page.reload_feedback = if removed.is_empty() {
+            "Reloaded the world".into()
+        } else {
+            format!("Reloaded the world, no longer has {}", removed.join(", "))
+        };
+
+        Box::new(page)
+    }
+
+    fn filter_world(self) -> (World, String) {
+        let included = self.included;
+        let max_tier = self.max_tier;
+
+        let world = self.world.filter_recipes(|RecipeId(index), recipe| {
+            included[index] && max_tier.is_none_or(|max_tier| recipe.within_tier(max_tier))
+        });
+
+        (world, self.world_path)
     }
 }
 
@@ -135,33 +239,130 @@ impl Page for LoadedPage {
     fn show(mut self: Box<Self>, ui: &mut Ui) -> Box<dyn Page> {
         ui.heading("Select Recipes");
 
-        let (back, confirm, open) = ui
+        if let Some(description) = &self.world.description {
+            ui.label(description);
+        }
+
+        let (back, confirm, mut open, project, inspect) = ui
             .horizontal(|ui| {
                 (
                     ui.button("Back").clicked(),
                     ui.button("Build").clicked(),
                     ui.button("Open Factory").clicked(),
+                    ui.button("Open Project")
+                        .on_hover_text("Combine several saved factories")
+                        .clicked(),
+                    ui.button("Inspect World").clicked(),
                 )
             })
             .inner;
 
-        ui.text_edit_singleline(&mut self.open_field);
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.open_field);
+            ui.label(&self.open_feedback);
+        });
 
-        let available_space = ui.available_rect_before_wrap();
+        let reload = world_reload_row(
+            ui,
+            &self.world_path,
+            self.world_modified,
+            &self.reload_feedback,
+        );
+
+        if let (Some(max_tier), Some(highest_tier)) = (&mut self.max_tier, self.world.max_tier()) {
+            let changed = ui
+                .add(Slider::new(max_tier, 0..=highest_tier).text("Max tier"))
+                .on_hover_text(
+                    "Recipes above this tier are removed, recipes without a tier are kept",
+                )
+                .changed();
 
-        let collumn_width = available_space.width() / 3.;
+            // only recipes with a tier follow the slider, so other choices are kept
+            if changed {
+                for (included, recipe) in self.included.iter_mut().zip(self.world.recipes.iter()) {
+                    if recipe.tier.is_some() {
+                        *included = recipe.within_tier(*max_tier);
+                    }
+                }
+            }
+        }
 
-        ui.push_id("Resources", |ui| {
-            let mut collumn = available_space;
-            collumn.set_width(collumn_width);
+        if let Some((missing, replacement)) = &mut self.open_remap {
+            let remap = ui
+                .horizontal(|ui| {
+                    ui.label(format!("Map recipe \"{}\" to", missing));
 
-            ui.allocate_ui_at_rect(collumn, |ui| {
+                    ComboBox::from_id_source("Remap")
+                        .selected_text(
+                            replacement
+                                .map(|recipe| self.world.name_of_recipe(recipe))
+                                .unwrap_or_default(),
+                        )
+                        .show_ui(ui, |ui| recipe_options(ui, &self.world, replacement));
+
+                    ui.add_enabled(replacement.is_some(), Button::new("Remap"))
+                        .on_hover_text("Rewrites the factory file with the new name")
+                        .clicked()
+                })
+                .inner;
+
+            if let (true, Some(replacement)) = (remap, *replacement) {
+                match remap_factory(
+                    &self.open_field,
+                    missing,
+                    self.world.name_of_recipe(replacement),
+                ) {
+                    Ok(()) => open = true,
+                    Err(_) => self.open_feedback = "Failed to rewrite the factory".into(),
+                }
+            }
+        }
+
+        let [resources_collumn, tags_collumn, recipes_collumn] =
+            columns(ui, &mut self.column_splits);
+
+        ui.push_id("Resources", |ui| {
+            ui.allocate_ui_at_rect(resources_collumn, |ui| {
                 ui.vertical(|ui| {
                     ui.heading("Resources");
 
+                    let unproducible = self.world.unproducible_resources();
+
+                    if !unproducible.is_empty() {
+                        ui.label(
+                            RichText::new(format!(
+                                "{} resources are not produced by any recipe",
+                                unproducible.len()
+                            ))
+                            .color(ui.visuals().warn_fg_color),
+                        );
+                    }
+
                     ScrollArea::new([false, true]).show(ui, |ui| {
-                        for resource in self.world.resources.iter() {
-                            ui.label(&resource.name);
+                        for (category, resources) in self.world.resources_by_category() {
+                            CollapsingHeader::new(category)
+                                .default_open(true)
+                                .show(ui, |ui| {
+                                    for resource in resources {
+                                        let name = self.world.name_of_resource(resource);
+
+                                        ui.horizontal(|ui| {
+                                            resource_swatch(ui, &self.world, resource);
+
+                                            if unproducible.contains(&resource) {
+                                                ui.label(
+                                                    RichText::new(format!(
+                                                        "{} (no producer)",
+                                                        name
+                                                    ))
+                                                    .color(ui.visuals().warn_fg_color),
+                                                );
+                                            } else {
+                                                ui.label(name);
+                                            }
+                                        });
+                                    }
+                                });
                         }
                     });
                 });
@@ -169,10 +370,7 @@ impl Page for LoadedPage {
         });
 
         ui.push_id("Tags", |ui| {
-            let mut collumn = available_space.translate(Vec2::new(collumn_width, 0.));
-            collumn.set_width(collumn_width);
-
-            ui.allocate_ui_at_rect(collumn, |ui| {
+            ui.allocate_ui_at_rect(tags_collumn, |ui| {
                 ui.vertical(|ui| {
                     ui.heading("Tags");
 
@@ -195,7 +393,11 @@ impl Page for LoadedPage {
                             ui.horizontal(|ui| {
                                 if ui.button("Add").clicked() {
                                     for (index, recipe) in self.world.recipes.iter().enumerate() {
-                                        if recipe.tags.contains(tag) {
+                                        let unlocked = self
+                                            .max_tier
+                                            .is_none_or(|max_tier| recipe.within_tier(max_tier));
+
+                                        if recipe.tags.contains(tag) && unlocked {
                                             self.included[index] = true;
                                         }
                                     }
@@ -218,17 +420,25 @@ impl Page for LoadedPage {
         });
 
         ui.push_id("Recipes", |ui| {
-            let mut collumn = available_space.translate(Vec2::new(collumn_width * 2., 0.));
-            collumn.set_width(collumn_width);
-
-            ui.allocate_ui_at_rect(collumn, |ui| {
+            ui.allocate_ui_at_rect(recipes_collumn, |ui| {
                 ui.vertical(|ui| {
                     ui.heading("Recipes");
 
-                    ScrollArea::new([false, true]).show(ui, |ui| {
-                        for (index, recipe) in self.world.recipes.iter().enumerate() {
-                            ui.checkbox(&mut self.included[index], &recipe.name);
+                    for &(a, b) in self.inverse_recipes.iter() {
+                        if self.included[a.0] && self.included[b.0] {
+                            ui.label(
+                                RichText::new(format!(
+                                    "{} and {} undo each other, consider removing one",
+                                    self.world.name_of_recipe(a),
+                                    self.world.name_of_recipe(b)
+                                ))
+                                .color(ui.visuals().warn_fg_color),
+                            );
                         }
+                    }
+
+                    ScrollArea::new([false, true]).show(ui, |ui| {
+                        recipe_checkboxes(ui, &self.world, &self.buildings, &mut self.included);
                     });
                 });
             });
@@ -238,14 +448,312 @@ impl Page for LoadedPage {
             return Box::new(LandingPage::new());
         }
 
+        if reload {
+            return self.reload();
+        }
+
         if confirm {
-            return Box::new(BuildFactoryPage::new(self.filter_world()));
+            let (world, world_path) = self.filter_world();
+            return Box::new(BuildFactoryPage::new(world, world_path));
+        }
+
+        if project {
+            return Box::new(ProjectPage::new(self.world.clone(), self));
+        }
+
+        if inspect {
+            return Box::new(InspectWorldPage {
+                loaded: self,
+                compared: None,
+                comparison_sort: ComparisonSort::Inputs,
+            });
         }
 
         if open {
-            if let Ok(factory) = load_factory(&self.world, &self.open_field) {
-                return Box::new(EditFactoryPage::new(self.world, factory));
+            self.open_remap = None;
+
+            match load_factory(&self.world, &self.open_field) {
+                Ok(factory) => return Box::new(EditFactoryPage::new(self.world, factory)),
+                Err(LoadFactoryError::IoError(_)) => self.open_feedback = "Io Error".into(),
+                Err(LoadFactoryError::JsonError(err)) => {
+                    self.open_feedback = json_error_message(&err)
+                }
+                Err(LoadFactoryError::BadRecipeName { recipe_name }) => {
+                    self.open_feedback = format!("Bad recipe name \"{}\"", recipe_name);
+                    self.open_remap = Some((recipe_name, None));
+                }
+            }
+        }
+
+        self
+    }
+}
+
+/// a read only view of everything in the world, returning to recipe selection when done
+struct InspectWorldPage {
+    loaded: Box<LoadedPage>,
+    /// the resource whose recipes are shown side by side in the "Compare Recipes" window
+    compared: Option<ResourceId>,
+    comparison_sort: ComparisonSort,
+}
+
+/// what the recipes in the comparison window are ordered by
+#[derive(Clone, Copy, PartialEq)]
+enum ComparisonSort {
+    Name,
+    Building,
+    /// the fewest total inputs per unit first
+    Inputs,
+    /// the least power per unit first
+    Power,
+}
+
+impl ComparisonSort {
+    const ALL: [ComparisonSort; 4] = [
+        ComparisonSort::Name,
+        ComparisonSort::Building,
+        ComparisonSort::Inputs,
+        ComparisonSort::Power,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ComparisonSort::Name => "Name",
+            ComparisonSort::Building => "Building",
+            ComparisonSort::Inputs => "Inputs",
+            ComparisonSort::Power => "Power",
+        }
+    }
+}
+
+impl InspectWorldPage {
+    /// a window with a row per recipe making the compared resource, with its rates per unit made
+    fn comparison_window(&mut self, ui: &mut Ui) {
+        let Some(resource) = self.compared else {
+            return;
+        };
+
+        let world = &self.loaded.world;
+        let mut open = true;
+
+        let mut comparisons = world.recipe_comparison(resource);
+
+        match self.comparison_sort {
+            ComparisonSort::Name => comparisons.sort_by(|a, b| {
+                world
+                    .name_of_recipe(a.recipe)
+                    .cmp(world.name_of_recipe(b.recipe))
+            }),
+            ComparisonSort::Building => comparisons.sort_by(|a, b| {
+                world.recipes[a.recipe.0]
+                    .building
+                    .cmp(&world.recipes[b.recipe.0].building)
+            }),
+            ComparisonSort::Inputs => {
+                comparisons.sort_by(|a, b| a.total_inputs().total_cmp(&b.total_inputs()))
             }
+            ComparisonSort::Power => {
+                comparisons.sort_by(|a, b| a.power.unwrap_or(0.).total_cmp(&b.power.unwrap_or(0.)))
+            }
+        }
+
+        let per_unit = |ui: &Ui, amounts: &[(ResourceId, f64)]| {
+            amounts
+                .iter()
+                .map(|&(other, amount)| {
+                    format!(
+                        "{} {}",
+                        format_number(ui, amount),
+                        world.name_of_resource(other)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        Window::new(format!(
+            "Recipes making {}",
+            world.name_of_resource(resource)
+        ))
+        .open(&mut open)
+        .vscroll(true)
+        .show(ui.ctx(), |ui| {
+            ComboBox::from_label("Sort by")
+                .selected_text(self.comparison_sort.name())
+                .show_ui(ui, |ui| {
+                    for sort in ComparisonSort::ALL {
+                        ui.selectable_value(&mut self.comparison_sort, sort, sort.name());
+                    }
+                });
+
+            ui.label("Inputs, byproducts and power are per unit made.");
+
+            Grid::new("Comparison").striped(true).show(ui, |ui| {
+                ui.strong("Recipe");
+                ui.strong("Building");
+                ui.strong("Inputs");
+                ui.strong("Byproducts");
+                if world.power.is_some() {
+                    ui.strong("Power");
+                }
+                ui.end_row();
+
+                for comparison in comparisons.iter() {
+                    ui.label(world.name_of_recipe(comparison.recipe));
+                    ui.label(
+                        world.recipes[comparison.recipe.0]
+                            .building
+                            .as_deref()
+                            .unwrap_or_default(),
+                    );
+                    ui.label(per_unit(ui, &comparison.inputs));
+                    ui.label(per_unit(ui, &comparison.byproducts));
+                    if let Some(power) = comparison.power {
+                        ui.label(format_number(ui, power));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+        if !open {
+            self.compared = None;
+        }
+    }
+}
+
+impl Page for InspectWorldPage {
+    fn show(mut self: Box<Self>, ui: &mut Ui) -> Box<dyn Page> {
+        ui.heading("Inspect World");
+
+        if ui.button("Back").clicked() {
+            return self.loaded;
+        }
+
+        self.comparison_window(ui);
+
+        let world = &self.loaded.world;
+        let mut compare = None;
+
+        let available_space = ui.available_rect_before_wrap();
+
+        let collumn_width = available_space.width() / 3.;
+
+        ui.push_id("Resources", |ui| {
+            let mut collumn = available_space;
+            collumn.set_width(collumn_width);
+
+            ui.allocate_ui_at_rect(collumn, |ui| {
+                ui.vertical(|ui| {
+                    ui.heading(format!("Resources ({})", world.resources.len()));
+
+                    let matches = search_field(ui);
+
+                    ScrollArea::new([false, true]).show(ui, |ui| {
+                        for (index, resource) in world.resources.iter().enumerate() {
+                            if !matches(&resource.name) {
+                                continue;
+                            }
+
+                            ui.horizontal(|ui| {
+                                resource_swatch(ui, world, ResourceId(index));
+                                ui.label(RichText::new(&resource.name).strong());
+
+                                if resource.is_fluid {
+                                    ui.label("fluid");
+                                }
+
+                                if let Some(category) = &resource.category {
+                                    ui.label(category);
+                                }
+
+                                if world.producers_of(ResourceId(index)).len() > 1
+                                    && ui
+                                        .small_button("Compare")
+                                        .on_hover_text("Compare the recipes making this resource")
+                                        .clicked()
+                                {
+                                    compare = Some(ResourceId(index));
+                                }
+                            });
+                        }
+                    });
+                });
+            });
+        });
+
+        ui.push_id("Recipes", |ui| {
+            let mut collumn = available_space.translate(Vec2::new(collumn_width, 0.));
+            collumn.set_width(collumn_width);
+
+            ui.allocate_ui_at_rect(collumn, |ui| {
+                ui.vertical(|ui| {
+                    ui.heading(format!("Recipes ({})", world.recipes.len()));
+
+                    let matches = search_field(ui);
+
+                    ScrollArea::new([false, true]).show(ui, |ui| {
+                        for (index, recipe) in world.recipes.iter().enumerate() {
+                            if !matches(&recipe.name) {
+                                continue;
+                            }
+
+                            ui.push_id(index, |ui| {
+                                ui.collapsing(world.recipe_label(RecipeId(index)), |ui| {
+                                    if let Some(building) = &recipe.building {
+                                        ui.label(format!("Building: {}", building));
+                                    }
+
+                                    if let Some(tier) = recipe.tier {
+                                        ui.label(format!("Tier: {}", tier));
+                                    }
+
+                                    if !recipe.tags.is_empty() {
+                                        ui.label(format!("Tags: {}", recipe.tags.join(", ")));
+                                    }
+
+                                    recipe_tooltip(ui, world, RecipeId(index));
+                                });
+                            });
+                        }
+                    });
+                });
+            });
+        });
+
+        ui.push_id("Tags", |ui| {
+            let mut collumn = available_space.translate(Vec2::new(collumn_width * 2., 0.));
+            collumn.set_width(collumn_width);
+
+            ui.allocate_ui_at_rect(collumn, |ui| {
+                ui.vertical(|ui| {
+                    let tags = world.tags();
+
+                    ui.heading(format!("Tags ({})", tags.len()));
+
+                    let matches = search_field(ui);
+
+                    ScrollArea::new([false, true]).show(ui, |ui| {
+                        for tag in tags.iter() {
+                            if !matches(tag) {
+                                continue;
+                            }
+
+                            ui.collapsing(tag, |ui| {
+                                for recipe in world.recipes.iter() {
+                                    if recipe.tags.contains(tag) {
+                                        ui.label(&recipe.name);
+                                    }
+                                }
+                            });
+                        }
+                    });
+                });
+            });
+        });
+
+        if compare.is_some() {
+            self.compared = compare;
         }
 
         self
@@ -0,0 +1,39 @@
+use std::{io::Write, path::Path, time::SystemTime};
+
+#[derive(Debug)]
+pub enum SaveError {
+    IoError(std::io::Error),
+}
+
+/// writes a file by writing a temporary file next to it and renaming it over the original
+///
+/// a failure part way through leaves the existing file untouched instead of truncated
+pub fn write_atomic(path: impl AsRef<Path>, contents: &[u8]) -> Result<(), SaveError> {
+    let path = path.as_ref();
+
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+
+        std::fs::rename(&temp_path, path)
+    })();
+
+    if let Err(err) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(SaveError::IoError(err));
+    }
+
+    Ok(())
+}
+
+/// when the file at `path` was last modified, none if it can't be read
+pub fn modified_time(path: impl AsRef<Path>) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
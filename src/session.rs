@@ -0,0 +1,532 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    builder::{Basket, Constraint, LoadRuleListError, Rule, RuleList, RuleListJson, VariableRef},
+    factory::{Factory, FactoryJson, LoadFactoryError},
+    save::{write_atomic, SaveError},
+    world::{load_world_from, LoadWorldError, RecipeId, ResourceId, VariableId, World},
+};
+
+/// everything needed to pick up a solve where it was left, for sharing a setup in a single file
+pub struct Session {
+    /// the path of the world file the session was built from
+    pub world_path: String,
+    /// the world with only the included recipes
+    pub world: World,
+    pub rule_lists: Vec<RuleList>,
+    /// each optimization with its weight and tier
+    pub optimizations: Vec<(VariableId, f64, u32)>,
+    /// the last solved factory, if there is one
+    pub factory: Option<Factory>,
+    /// recipes unchecked in the builder, which may not run
+    pub disabled: Vec<RecipeId>,
+    /// recipes the builder requires to run
+    pub required: Vec<RecipeId>,
+    /// each recipe cap with its machine count as typed
+    pub recipe_caps: Vec<(RecipeId, String)>,
+    /// each import with its cap as typed
+    pub imports: Vec<(ResourceId, String)>,
+    /// each ratio with its factor as typed
+    pub ratios: Vec<(VariableId, VariableId, String)>,
+    /// each percentage with its value as typed
+    pub percentages: Vec<(VariableId, VariableId, String)>,
+    /// each tag rule with its rate as typed
+    pub tag_rules: Vec<(String, Constraint, String)>,
+    /// recipes kept at a machine count
+    pub pinned: Vec<(RecipeId, f64)>,
+    /// minimize raw resources when there are no optimizations
+    pub minimize_raw: bool,
+    /// maximize the sink points of surplus resources
+    pub sink_points: bool,
+    /// maximize the smallest of the optimization variables
+    pub balance_outputs: bool,
+    /// recipes using fewer machines than this are pruned, zero disables pruning
+    pub prune_threshold: f64,
+    /// keep recipes whose rate rounds to zero
+    pub show_marginal: bool,
+    /// reject solutions left short by rounding
+    pub strict_balance: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionJson {
+    world_path: String,
+    /// the world path is the game's docs file rather than a world file
+    #[serde(default)]
+    from_game_docs: bool,
+    /// the names of the included recipes
+    recipes: Vec<String>,
+    rule_lists: Vec<RuleListJson>,
+    #[serde(default)]
+    optimizations: Vec<(VariableRef, f64)>,
+    /// the tier of each optimization in order, sessions saved without tiers put every optimization in tier 1
+    #[serde(default)]
+    optimization_tiers: Vec<u32>,
+    #[serde(default)]
+    factory: Option<FactoryJson>,
+    #[serde(default)]
+    disabled: Vec<String>,
+    #[serde(default)]
+    required: Vec<String>,
+    #[serde(default)]
+    recipe_caps: Vec<(String, String)>,
+    #[serde(default)]
+    imports: Vec<(String, String)>,
+    #[serde(default)]
+    ratios: Vec<(VariableRef, VariableRef, String)>,
+    #[serde(default)]
+    percentages: Vec<(VariableRef, VariableRef, String)>,
+    #[serde(default)]
+    tag_rules: Vec<(String, Constraint, String)>,
+    #[serde(default)]
+    pinned: Vec<(String, f64)>,
+    #[serde(default = "default_minimize_raw")]
+    minimize_raw: bool,
+    #[serde(default)]
+    sink_points: bool,
+    #[serde(default)]
+    balance_outputs: bool,
+    #[serde(default)]
+    prune_threshold: f64,
+    #[serde(default)]
+    show_marginal: bool,
+    #[serde(default)]
+    strict_balance: bool,
+}
+
+fn default_minimize_raw() -> bool {
+    true
+}
+
+#[derive(Debug)]
+pub enum LoadSessionError {
+    IoError(std::io::Error),
+    JsonError(serde_json::Error),
+    /// the world file the session refers to doesn't exist
+    MissingWorld {
+        world_path: String,
+    },
+    WorldError(LoadWorldError),
+    /// an included recipe is not in the world
+    BadRecipeName {
+        recipe_name: String,
+    },
+    RuleListError(LoadRuleListError),
+    FactoryError(LoadFactoryError),
+}
+
+pub fn save_session(session: &Session, path: impl AsRef<std::path::Path>) -> Result<(), SaveError> {
+    let world = &session.world;
+
+    let session_json = SessionJson {
+        world_path: session.world_path.clone(),
+        from_game_docs: world.from_game_docs,
+        recipes: world
+            .recipes
+            .iter()
+            .map(|recipe| recipe.name.clone())
+            .collect(),
+        rule_lists: session
+            .rule_lists
+            .iter()
+            .map(|rule_list| RuleListJson::new(world, rule_list))
+            .collect(),
+        optimizations: session
+            .optimizations
+            .iter()
+            .map(|&(variable, coefficient, _)| (VariableRef::new(world, variable), coefficient))
+            .collect(),
+        optimization_tiers: session
+            .optimizations
+            .iter()
+            .map(|&(_, _, tier)| tier)
+            .collect(),
+        factory: session
+            .factory
+            .as_ref()
+            .map(|factory| FactoryJson::new(world, factory)),
+        disabled: session
+            .disabled
+            .iter()
+            .map(|&recipe| world.name_of_recipe(recipe).into())
+            .collect(),
+        required: session
+            .required
+            .iter()
+            .map(|&recipe| world.name_of_recipe(recipe).into())
+            .collect(),
+        recipe_caps: session
+            .recipe_caps
+            .iter()
+            .map(|(recipe, max)| (world.name_of_recipe(*recipe).into(), max.clone()))
+            .collect(),
+        imports: session
+            .imports
+            .iter()
+            .map(|(resource, cap)| (world.name_of_resource(*resource).into(), cap.clone()))
+            .collect(),
+        ratios: session
+            .ratios
+            .iter()
+            .map(|(a, b, factor)| {
+                (
+                    VariableRef::new(world, *a),
+                    VariableRef::new(world, *b),
+                    factor.clone(),
+                )
+            })
+            .collect(),
+        percentages: session
+            .percentages
+            .iter()
+            .map(|(a, b, percentage)| {
+                (
+                    VariableRef::new(world, *a),
+                    VariableRef::new(world, *b),
+                    percentage.clone(),
+                )
+            })
+            .collect(),
+        tag_rules: session.tag_rules.clone(),
+        pinned: session
+            .pinned
+            .iter()
+            .map(|&(recipe, machines)| (world.name_of_recipe(recipe).into(), machines))
+            .collect(),
+        minimize_raw: session.minimize_raw,
+        sink_points: session.sink_points,
+        balance_outputs: session.balance_outputs,
+        prune_threshold: session.prune_threshold,
+        show_marginal: session.show_marginal,
+        strict_balance: session.strict_balance,
+    };
+
+    write_atomic(
+        path,
+        serde_json::to_string_pretty(&session_json)
+            .expect("Failed to convert to json")
+            .as_bytes(),
+    )
+}
+
+pub fn load_session(path: impl AsRef<std::path::Path>) -> Result<Session, LoadSessionError> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) => return Err(LoadSessionError::IoError(err)),
+    };
+
+    let session_json: SessionJson = match serde_json::from_reader(std::io::BufReader::new(file)) {
+        Ok(session) => session,
+        Err(err) => return Err(LoadSessionError::JsonError(err)),
+    };
+
+    if !std::path::Path::new(&session_json.world_path).exists() {
+        return Err(LoadSessionError::MissingWorld {
+            world_path: session_json.world_path,
+        });
+    }
+
+    let world = load_world_from(&session_json.world_path, session_json.from_game_docs)
+        .map_err(LoadSessionError::WorldError)?;
+
+    let mut included = Vec::new();
+
+    for recipe_name in session_json.recipes {
+        let Some(recipe) = world.recipe_id_of_name(&recipe_name) else {
+            return Err(LoadSessionError::BadRecipeName { recipe_name });
+        };
+
+        included.push(recipe);
+    }
+
+    let world = world.filter_recipes(|recipe, _| included.contains(&recipe));
+
+    let mut rule_lists = Vec::new();
+
+    for rule_list in session_json.rule_lists {
+        rule_lists.push(
+            rule_list
+                .into_rule_list(&world)
+                .map_err(LoadSessionError::RuleListError)?,
+        );
+    }
+
+    let mut optimizations = Vec::new();
+
+    for (index, (variable, coefficient)) in session_json.optimizations.into_iter().enumerate() {
+        let variable = variable
+            .resolve(&world)
+            .map_err(LoadSessionError::RuleListError)?;
+        let tier = session_json
+            .optimization_tiers
+            .get(index)
+            .copied()
+            .unwrap_or(1);
+
+        optimizations.push((variable, coefficient, tier));
+    }
+
+    let factory = match session_json.factory {
+        Some(factory) => Some(
+            factory
+                .into_factory(&world)
+                .map_err(LoadSessionError::FactoryError)?,
+        ),
+        None => None,
+    };
+
+    let recipe_of_name = |recipe_name: String| match world.recipe_id_of_name(&recipe_name) {
+        Some(recipe) => Ok(recipe),
+        None => Err(LoadSessionError::BadRecipeName { recipe_name }),
+    };
+
+    let resolve = |variable: VariableRef| {
+        variable
+            .resolve(&world)
+            .map_err(LoadSessionError::RuleListError)
+    };
+
+    let mut disabled = Vec::new();
+    for recipe_name in session_json.disabled {
+        disabled.push(recipe_of_name(recipe_name)?);
+    }
+
+    let mut required = Vec::new();
+    for recipe_name in session_json.required {
+        required.push(recipe_of_name(recipe_name)?);
+    }
+
+    let mut recipe_caps = Vec::new();
+    for (recipe_name, max) in session_json.recipe_caps {
+        recipe_caps.push((recipe_of_name(recipe_name)?, max));
+    }
+
+    let mut imports = Vec::new();
+    for (resource_name, cap) in session_json.imports {
+        let Some(resource) = world.resource_id_of_name(&resource_name) else {
+            return Err(LoadSessionError::RuleListError(
+                LoadRuleListError::BadResourceName { resource_name },
+            ));
+        };
+
+        imports.push((resource, cap));
+    }
+
+    let mut ratios = Vec::new();
+    for (a, b, factor) in session_json.ratios {
+        ratios.push((resolve(a)?, resolve(b)?, factor));
+    }
+
+    let mut percentages = Vec::new();
+    for (a, b, percentage) in session_json.percentages {
+        percentages.push((resolve(a)?, resolve(b)?, percentage));
+    }
+
+    let mut pinned = Vec::new();
+    for (recipe_name, machines) in session_json.pinned {
+        pinned.push((recipe_of_name(recipe_name)?, machines));
+    }
+
+    Ok(Session {
+        world_path: session_json.world_path,
+        world,
+        rule_lists,
+        optimizations,
+        factory,
+        disabled,
+        required,
+        recipe_caps,
+        imports,
+        ratios,
+        percentages,
+        tag_rules: session_json.tag_rules,
+        pinned,
+        minimize_raw: session_json.minimize_raw,
+        sink_points: session_json.sink_points,
+        balance_outputs: session_json.balance_outputs,
+        prune_threshold: session_json.prune_threshold,
+        show_marginal: session_json.show_marginal,
+        strict_balance: session_json.strict_balance,
+    })
+}
+
+/// moves a session onto a fresh copy of its world file, matching resources and recipes by name
+///
+/// only the recipes the session already had are kept. rules, optimizations, basket resources,
+/// factory recipes and builder settings naming something the new world doesn't have are dropped,
+/// and the dropped names are returned alongside the session
+pub fn reload_session(session: Session) -> Result<(Session, Vec<String>), LoadWorldError> {
+    let old_world = session.world;
+
+    let world = load_world_from(&session.world_path, old_world.from_game_docs)?.filter_recipes(
+        |_, recipe| {
+            old_world
+                .recipes
+                .iter()
+                .any(|old_recipe| old_recipe.name == recipe.name)
+        },
+    );
+
+    let mut missing = Vec::new();
+
+    let mut resolve = |variable: VariableId| {
+        let found = match VariableRef::new(&old_world, variable) {
+            VariableRef::Resource(name) => match world.resource_id_of_name(&name) {
+                Some(resource) => Ok(resource.variable_id()),
+                None => Err(name),
+            },
+            VariableRef::Recipe(name) => match world.recipe_id_of_name(&name) {
+                Some(recipe) => Ok(recipe.variable_id()),
+                None => Err(name),
+            },
+        };
+
+        match found {
+            Ok(variable) => Some(variable),
+            Err(name) => {
+                if !missing.contains(&name) {
+                    missing.push(name);
+                }
+
+                None
+            }
+        }
+    };
+
+    let rule_lists = session
+        .rule_lists
+        .into_iter()
+        .map(|rule_list| RuleList {
+            rules: rule_list
+                .rules
+                .into_iter()
+                .filter_map(|rule| {
+                    Some(Rule {
+                        variable: resolve(rule.variable)?,
+                        ..rule
+                    })
+                })
+                .collect(),
+            optimizations: rule_list
+                .optimizations
+                .into_iter()
+                .filter_map(|(variable, coefficient)| Some((resolve(variable)?, coefficient)))
+                .collect(),
+            baskets: rule_list
+                .baskets
+                .into_iter()
+                .map(|basket| Basket {
+                    resources: basket
+                        .resources
+                        .into_iter()
+                        .filter_map(
+                            |(resource, amount)| match resolve(resource.variable_id())? {
+                                VariableId::Resource(resource) => Some((resource, amount)),
+                                VariableId::Recipe(_) => None,
+                            },
+                        )
+                        .collect(),
+                    ..basket
+                })
+                .filter(|basket| !basket.resources.is_empty())
+                .collect(),
+        })
+        .collect();
+
+    let optimizations = session
+        .optimizations
+        .into_iter()
+        .filter_map(|(variable, coefficient, tier)| Some((resolve(variable)?, coefficient, tier)))
+        .collect();
+
+    let factory = session.factory.map(|factory| Factory {
+        recipes: factory
+            .recipes
+            .into_iter()
+            .filter_map(|(recipe, rate)| match resolve(recipe.variable_id())? {
+                VariableId::Recipe(recipe) => Some((recipe, rate)),
+                VariableId::Resource(_) => None,
+            })
+            .collect(),
+    });
+
+    let mut resolve_recipe = |recipe: RecipeId| match resolve(recipe.variable_id())? {
+        VariableId::Recipe(recipe) => Some(recipe),
+        VariableId::Resource(_) => None,
+    };
+
+    let disabled = session
+        .disabled
+        .into_iter()
+        .filter_map(&mut resolve_recipe)
+        .collect();
+
+    let required = session
+        .required
+        .into_iter()
+        .filter_map(&mut resolve_recipe)
+        .collect();
+
+    let recipe_caps = session
+        .recipe_caps
+        .into_iter()
+        .filter_map(|(recipe, max)| Some((resolve_recipe(recipe)?, max)))
+        .collect();
+
+    let pinned = session
+        .pinned
+        .into_iter()
+        .filter_map(|(recipe, machines)| Some((resolve_recipe(recipe)?, machines)))
+        .collect();
+
+    let imports = session
+        .imports
+        .into_iter()
+        .filter_map(|(resource, cap)| match resolve(resource.variable_id())? {
+            VariableId::Resource(resource) => Some((resource, cap)),
+            VariableId::Recipe(_) => None,
+        })
+        .collect();
+
+    let ratios = session
+        .ratios
+        .into_iter()
+        .filter_map(|(a, b, factor)| Some((resolve(a)?, resolve(b)?, factor)))
+        .collect();
+
+    let percentages = session
+        .percentages
+        .into_iter()
+        .filter_map(|(a, b, percentage)| Some((resolve(a)?, resolve(b)?, percentage)))
+        .collect();
+
+    let tags = world.tags();
+    let mut tag_rules = Vec::new();
+    for (tag, constraint, value) in session.tag_rules {
+        if tags.contains(&tag) {
+            tag_rules.push((tag, constraint, value));
+        } else if !missing.contains(&tag) {
+            missing.push(tag);
+        }
+    }
+
+    Ok((
+        Session {
+            world_path: session.world_path,
+            world,
+            rule_lists,
+            optimizations,
+            factory,
+            disabled,
+            required,
+            recipe_caps,
+            imports,
+            ratios,
+            percentages,
+            tag_rules,
+            pinned,
+            ..session
+        },
+        missing,
+    ))
+}
@@ -1,34 +1,172 @@
+use std::collections::HashMap;
+
+use log::warn;
 use serde::{Deserialize, Serialize};
 
 /// a resource id within a world
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ResourceId(pub usize);
 
 /// a recipe id within a world
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RecipeId(pub usize);
 
 /// an id that is either a resource or a recipe
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum VariableId {
     Resource(ResourceId),
     Recipe(RecipeId),
 }
 
+#[derive(Clone)]
 pub struct Resource {
     pub name: String,
+    /// fluids are carried by pipes instead of belts
+    pub is_fluid: bool,
+    /// other names for this resource, so rule lists written for other worlds still load
+    pub aliases: Vec<String>,
+    /// a group like "Ores" or "Parts" the resource is listed under
+    pub category: Option<String>,
+    /// an rgb color the resource is marked with
+    pub color: Option<[u8; 3]>,
+    /// sink points awarded per unit of surplus
+    pub points: Option<f64>,
 }
 
+#[derive(Clone)]
 pub struct Recipe {
     pub name: String,
     pub tags: Vec<String>,
     pub rates: Vec<(ResourceId, f64)>,
+    /// biases the solver towards (positive) or away from (negative) this recipe
+    pub preference: f64,
+    /// an annotation from the world file
+    pub note: Option<String>,
+    /// the type of building that runs this recipe
+    pub building: Option<String>,
+    /// other names for this recipe, so rule lists written for other worlds still load
+    pub aliases: Vec<String>,
+    /// the tier the recipe unlocks at, recipes without one are always available
+    pub tier: Option<u32>,
+    /// the recipe this is the amplified variant of
+    ///
+    /// an amplified variant is loaded as a recipe of its own with the same inputs, more of every
+    /// output and a consumable used up on top, so the solver weighs it as a separate variable and
+    /// picks whichever mix of the two is best. both draw their shared ingredients from the same
+    /// resource balance, and disabling the base recipe disables the variant with it
+    pub amplifies: Option<RecipeId>,
+}
+
+impl Recipe {
+    /// whether the recipe is unlocked by the time `max_tier` is reached
+    pub fn within_tier(&self, max_tier: u32) -> bool {
+        self.tier.is_none_or(|tier| tier <= max_tier)
+    }
+
+    /// the resource the recipe makes the most of, none if it makes nothing
+    pub fn primary_output(&self) -> Option<ResourceId> {
+        self.rates
+            .iter()
+            .filter(|(_, rate)| *rate > 0.)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|&(resource, _)| resource)
+    }
+}
+
+/// a recipe's rates per unit of one of its outputs, from [World::recipe_comparison]
+pub struct RecipeComparison {
+    pub recipe: RecipeId,
+    /// each resource used with how much is used per unit
+    pub inputs: Vec<(ResourceId, f64)>,
+    /// each other resource made with how much is made per unit
+    pub byproducts: Vec<(ResourceId, f64)>,
+    /// the power used per unit, negative if the recipe generates power, none if the world has no power resource
+    pub power: Option<f64>,
+}
+
+impl RecipeComparison {
+    /// the sum of the inputs per unit, a rough measure of how expensive the recipe is
+    pub fn total_inputs(&self) -> f64 {
+        self.inputs.iter().map(|(_, amount)| amount).sum()
+    }
 }
 
-#[derive(Default)]
+#[derive(Clone)]
 pub struct World {
     pub resources: Vec<Resource>,
     pub recipes: Vec<Recipe>,
+    /// the most a single belt can carry per minute
+    pub belt_limit: f64,
+    /// the most a single pipe can carry per minute
+    pub pipe_limit: f64,
+    /// the resource representing power, if any
+    ///
+    /// power is an ordinary resource, generators produce it and machines consume it,
+    /// so a factory is powered by adding a rule that its net power is greater than zero
+    pub power: Option<ResourceId>,
+    /// an annotation from the world file
+    pub description: Option<String>,
+    /// the floor area of each type of building, in square meters
+    pub footprints: HashMap<String, f64>,
+    /// groups of resource nodes, which cap how much of their resource can be extracted
+    pub nodes: Vec<NodeGroup>,
+    /// how much a normal node yields per minute, other purities scale this
+    pub node_rate: f64,
+    /// the world was imported from the game's docs file rather than loaded from a world file,
+    /// so it has to be read again with [import_game_docs]
+    pub from_game_docs: bool,
+}
+
+/// a number of resource nodes of the same resource and purity
+#[derive(Clone)]
+pub struct NodeGroup {
+    pub resource: ResourceId,
+    pub purity: Purity,
+    pub count: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Purity {
+    Impure,
+    Normal,
+    Pure,
+}
+
+impl Purity {
+    /// how many normal nodes a node of this purity is worth
+    pub fn multiplier(self) -> f64 {
+        match self {
+            Purity::Impure => 0.5,
+            Purity::Normal => 1.,
+            Purity::Pure => 2.,
+        }
+    }
+}
+
+/// the throughput of a mk5 belt
+pub const DEFAULT_BELT_LIMIT: f64 = 780.;
+
+/// the throughput of a mk2 pipe
+pub const DEFAULT_PIPE_LIMIT: f64 = 600.;
+
+/// what a mk1 miner extracts from a normal node
+pub const DEFAULT_NODE_RATE: f64 = 60.;
+
+impl Default for World {
+    fn default() -> Self {
+        World {
+            resources: Vec::new(),
+            recipes: Vec::new(),
+            belt_limit: DEFAULT_BELT_LIMIT,
+            pipe_limit: DEFAULT_PIPE_LIMIT,
+            power: None,
+            description: None,
+            footprints: HashMap::new(),
+            nodes: Vec::new(),
+            node_rate: DEFAULT_NODE_RATE,
+            from_game_docs: false,
+        }
+    }
 }
 
 impl ResourceId {
@@ -44,10 +182,16 @@ impl RecipeId {
 }
 
 impl World {
+    /// finds a resource by name, falling back to aliases if no resource has that exact name
     pub fn resource_id_of_name(&self, resource_name: &str) -> Option<ResourceId> {
         self.resources
             .iter()
-            .position(|Resource { name }| *name == *resource_name)
+            .position(|resource| resource.name == *resource_name)
+            .or_else(|| {
+                self.resources.iter().position(|resource| {
+                    resource.aliases.iter().any(|alias| alias == resource_name)
+                })
+            })
             .map(|index| ResourceId(index))
     }
 
@@ -59,10 +203,25 @@ impl World {
             .name
     }
 
+    /// the unit amounts of a resource are measured in, "m³" for fluids and nothing for items which are counted
+    pub fn unit_of(&self, resource: ResourceId) -> &'static str {
+        if self.resources[resource.0].is_fluid {
+            "m³"
+        } else {
+            ""
+        }
+    }
+
+    /// finds a recipe by name, falling back to aliases if no recipe has that exact name
     pub fn recipe_id_of_name(&self, recipe_name: &str) -> Option<RecipeId> {
         self.recipes
             .iter()
             .position(|Recipe { name, .. }| *name == *recipe_name)
+            .or_else(|| {
+                self.recipes.iter().position(|Recipe { aliases, .. }| {
+                    aliases.iter().any(|alias| alias == recipe_name)
+                })
+            })
             .map(|index| RecipeId(index))
     }
 
@@ -74,6 +233,20 @@ impl World {
             .name
     }
 
+    /// the recipe's name followed by its primary output, like "Alternate: Pure Iron Ingot (Iron Ingot)"
+    ///
+    /// the output is left off when the recipe makes nothing or is already named after it
+    pub fn recipe_label(&self, recipe: RecipeId) -> String {
+        let name = self.name_of_recipe(recipe);
+
+        match self.recipes[recipe.0].primary_output() {
+            Some(output) if self.name_of_resource(output) != name => {
+                format!("{} ({})", name, self.name_of_resource(output))
+            }
+            _ => name.into(),
+        }
+    }
+
     pub fn name_of_variable(&self, variable: VariableId) -> String {
         match variable {
             VariableId::Resource(resource) => {
@@ -82,20 +255,408 @@ impl World {
             VariableId::Recipe(recipe) => format!("Recipe {}", self.name_of_recipe(recipe)),
         }
     }
+
+    /// resources that no recipe produces, these can only be brought into a factory
+    pub fn raw_resources(&self) -> Vec<ResourceId> {
+        self.unproducible_resources()
+    }
+
+    /// resources that never appear with a positive rate in any recipe
+    ///
+    /// in a world with extraction recipes these are usually typos or missing recipes
+    pub fn unproducible_resources(&self) -> Vec<ResourceId> {
+        let mut produced = vec![false; self.resources.len()];
+
+        for recipe in self.recipes.iter() {
+            for &(ResourceId(index), rate) in recipe.rates.iter() {
+                if rate > 0. {
+                    produced[index] = true;
+                }
+            }
+        }
+
+        produced
+            .into_iter()
+            .enumerate()
+            .filter(|(_, produced)| !produced)
+            .map(|(index, _)| ResourceId(index))
+            .collect()
+    }
+
+    /// the recipes with a positive rate of a resource
+    pub fn producers_of(&self, resource: ResourceId) -> Vec<RecipeId> {
+        self.recipes
+            .iter()
+            .enumerate()
+            .filter(|(_, recipe)| {
+                recipe
+                    .rates
+                    .iter()
+                    .any(|&(other, rate)| other == resource && rate > 0.)
+            })
+            .map(|(index, _)| RecipeId(index))
+            .collect()
+    }
+
+    /// the recipes making a resource with their rates scaled to one unit of it, for choosing between alternates
+    pub fn recipe_comparison(&self, resource: ResourceId) -> Vec<RecipeComparison> {
+        self.producers_of(resource)
+            .into_iter()
+            .map(|recipe| {
+                let rates = &self.recipes[recipe.0].rates;
+
+                let output: f64 = rates
+                    .iter()
+                    .filter(|&&(other, _)| other == resource)
+                    .map(|&(_, rate)| rate)
+                    .sum();
+
+                let mut comparison = RecipeComparison {
+                    recipe,
+                    inputs: Vec::new(),
+                    byproducts: Vec::new(),
+                    power: self.power.map(|_| 0.),
+                };
+
+                for &(other, rate) in rates.iter() {
+                    if other == resource {
+                        continue;
+                    }
+
+                    let per_unit = rate / output;
+
+                    if Some(other) == self.power {
+                        comparison.power = Some(-per_unit);
+                    } else if rate < 0. {
+                        comparison.inputs.push((other, -per_unit));
+                    } else {
+                        comparison.byproducts.push((other, per_unit));
+                    }
+                }
+
+                comparison
+            })
+            .collect()
+    }
+
+    /// the resources grouped by category, in the order each category first appears
+    ///
+    /// resources without a category are grouped last under "Uncategorized"
+    pub fn resources_by_category(&self) -> Vec<(&str, Vec<ResourceId>)> {
+        let mut categories: Vec<(&str, Vec<ResourceId>)> = Vec::new();
+        let mut uncategorized = Vec::new();
+
+        for (index, resource) in self.resources.iter().enumerate() {
+            let Some(category) = &resource.category else {
+                uncategorized.push(ResourceId(index));
+                continue;
+            };
+
+            match categories.iter_mut().find(|(other, _)| other == category) {
+                Some((_, resources)) => resources.push(ResourceId(index)),
+                None => categories.push((category, vec![ResourceId(index)])),
+            }
+        }
+
+        if !uncategorized.is_empty() {
+            categories.push(("Uncategorized", uncategorized));
+        }
+
+        categories
+    }
+
+    /// the highest tier any recipe unlocks at, none if no recipe has a tier
+    pub fn max_tier(&self) -> Option<u32> {
+        self.recipes.iter().filter_map(|recipe| recipe.tier).max()
+    }
+
+    /// recipes without any rates, which do nothing
+    pub fn empty_recipes(&self) -> Vec<RecipeId> {
+        self.recipes
+            .iter()
+            .enumerate()
+            .filter(|(_, recipe)| recipe.rates.is_empty())
+            .map(|(index, _)| RecipeId(index))
+            .collect()
+    }
+
+    /// pairs of recipes where one exactly undoes the other, up to a scale
+    ///
+    /// running both costs nothing, so the solver can loop them freely which can make solutions
+    /// degenerate or unbounded. disabling one of each pair avoids this
+    pub fn detect_recipe_cycles_zero_cost(&self) -> Vec<(RecipeId, RecipeId)> {
+        let mut pairs = Vec::new();
+
+        for (a_index, a) in self.recipes.iter().enumerate() {
+            for (b_index, b) in self.recipes.iter().enumerate().skip(a_index + 1) {
+                if a.rates.is_empty() || a.rates.len() != b.rates.len() {
+                    continue;
+                }
+
+                let (first_resource, first_rate) = a.rates[0];
+
+                let Some(&(_, other_rate)) = b
+                    .rates
+                    .iter()
+                    .find(|(resource, _)| *resource == first_resource)
+                else {
+                    continue;
+                };
+
+                // how many times a the reverse of b is
+                let scale = -other_rate / first_rate;
+
+                if scale <= 0. || !scale.is_finite() {
+                    continue;
+                }
+
+                let inverse = a.rates.iter().all(|&(resource, rate)| {
+                    b.rates.iter().any(|&(other, other_rate)| {
+                        other == resource
+                            && (other_rate + rate * scale).abs() <= 1e-9 * other_rate.abs()
+                    })
+                });
+
+                if inverse {
+                    pairs.push((RecipeId(a_index), RecipeId(b_index)));
+                }
+            }
+        }
+
+        pairs
+    }
+
+    /// the recipes grouped by building in alphabetical order
+    ///
+    /// recipes without a building are grouped last under "Other"
+    pub fn recipes_by_building(&self) -> Vec<(String, Vec<RecipeId>)> {
+        let mut buildings: Vec<(String, Vec<RecipeId>)> = Vec::new();
+        let mut other = Vec::new();
+
+        for (index, recipe) in self.recipes.iter().enumerate() {
+            let Some(building) = &recipe.building else {
+                other.push(RecipeId(index));
+                continue;
+            };
+
+            match buildings.iter_mut().find(|(name, _)| name == building) {
+                Some((_, recipes)) => recipes.push(RecipeId(index)),
+                None => buildings.push((building.clone(), vec![RecipeId(index)])),
+            }
+        }
+
+        buildings.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        if !other.is_empty() {
+            buildings.push(("Other".into(), other));
+        }
+
+        buildings
+    }
+
+    /// every distinct recipe tag, in the order they first appear
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = Vec::new();
+
+        for recipe in self.recipes.iter() {
+            for tag in recipe.tags.iter() {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+
+        tags
+    }
+
+    /// a copy of this world with only the recipes for which `keep` is true
+    ///
+    /// amplified variants are pointed at their base's new id, or unlinked if the base was removed
+    pub fn filter_recipes(self, mut keep: impl FnMut(RecipeId, &Recipe) -> bool) -> World {
+        // the new id of each recipe, none for removed recipes
+        let mut new_ids = Vec::with_capacity(self.recipes.len());
+        let mut kept = 0;
+
+        for (index, recipe) in self.recipes.iter().enumerate() {
+            if keep(RecipeId(index), recipe) {
+                new_ids.push(Some(RecipeId(kept)));
+                kept += 1;
+            } else {
+                new_ids.push(None);
+            }
+        }
+
+        World {
+            recipes: self
+                .recipes
+                .into_iter()
+                .zip(new_ids.iter())
+                .filter(|(_, new_id)| new_id.is_some())
+                .map(|(recipe, _)| Recipe {
+                    amplifies: recipe.amplifies.and_then(|RecipeId(base)| new_ids[base]),
+                    ..recipe
+                })
+                .collect(),
+            ..self
+        }
+    }
+
+    /// iterates over all the fluid resources
+    pub fn fluids(&self) -> impl Iterator<Item = ResourceId> + '_ {
+        self.resources
+            .iter()
+            .enumerate()
+            .filter(|(_, resource)| resource.is_fluid)
+            .map(|(index, _)| ResourceId(index))
+    }
+
+    /// how many normal nodes the nodes of a resource are worth, none if the world declares no nodes for it
+    pub fn node_equivalents(&self, resource: ResourceId) -> Option<f64> {
+        let groups: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|group| group.resource == resource)
+            .collect();
+
+        if groups.is_empty() {
+            return None;
+        }
+
+        Some(
+            groups
+                .iter()
+                .map(|group| group.count as f64 * group.purity.multiplier())
+                .sum(),
+        )
+    }
+
+    /// the most of a resource that can be extracted per minute from its nodes, none if it has none
+    pub fn extraction_cap(&self, resource: ResourceId) -> Option<f64> {
+        Some(self.node_equivalents(resource)? * self.node_rate)
+    }
+
+    /// a note like "using 7 of 12 available node-equivalents" for a resource with nodes and a net rate
+    pub fn node_usage(&self, resource: ResourceId, rate: f64) -> Option<String> {
+        let available = self.node_equivalents(resource)?;
+        let used = (-rate).max(0.) / self.node_rate;
+
+        Some(format!(
+            "using {} of {} available node-equivalents",
+            (used * 100.).round() / 100.,
+            available
+        ))
+    }
+
+    /// a warning like "needs 3 belts" if a flow of a resource
+    /// is more than a single belt or pipe can carry
+    pub fn throughput_warning(&self, resource: ResourceId, rate: f64) -> Option<String> {
+        let (limit, carrier) = if self.resources[resource.0].is_fluid {
+            (self.pipe_limit, "pipes")
+        } else {
+            (self.belt_limit, "belts")
+        };
+
+        let needed = (rate.abs() / limit).ceil();
+
+        if needed > 1. {
+            Some(format!("needs {} {}", needed, carrier))
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 struct WorldJson {
-    resources: Vec<String>,
+    resources: Vec<ResourceJson>,
     recipes: Vec<RecipeJson>,
+    #[serde(default = "default_belt_limit")]
+    belt_limit: f64,
+    #[serde(default = "default_pipe_limit")]
+    pipe_limit: f64,
+    #[serde(default)]
+    power: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    footprints: HashMap<String, f64>,
+    #[serde(default)]
+    nodes: Vec<NodeGroupJson>,
+    #[serde(default = "default_node_rate")]
+    node_rate: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NodeGroupJson {
+    resource: String,
+    purity: Purity,
+    count: u32,
+}
+
+fn default_belt_limit() -> f64 {
+    DEFAULT_BELT_LIMIT
+}
+
+fn default_node_rate() -> f64 {
+    DEFAULT_NODE_RATE
+}
+
+fn default_pipe_limit() -> f64 {
+    DEFAULT_PIPE_LIMIT
+}
+
+/// a resource is either just its name or an object with extra properties
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ResourceJson {
+    Name(String),
+    Full {
+        name: String,
+        #[serde(default)]
+        is_fluid: bool,
+        #[serde(default)]
+        aliases: Vec<String>,
+        #[serde(default)]
+        category: Option<String>,
+        #[serde(default)]
+        color: Option<[u8; 3]>,
+        #[serde(default)]
+        points: Option<f64>,
+    },
 }
 
 #[derive(Serialize, Deserialize)]
 struct RecipeJson {
     name: String,
+    #[serde(default)]
     tags: Vec<String>,
     per_minute: f64,
+    #[serde(default)]
     rates: Vec<(String, f64)>,
+    #[serde(default)]
+    preference: f64,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    building: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    tier: Option<u32>,
+    /// the name of a recipe to inherit rates and tags from when this recipe has none of its own
+    #[serde(default)]
+    extends: Option<String>,
+    /// a boosted way of running the recipe, loaded as its own recipe named "<name> (Amplified)"
+    #[serde(default)]
+    amplified: Option<AmplifiedJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AmplifiedJson {
+    /// how many times the normal outputs the variant makes
+    output_multiplier: f64,
+    /// the resource used up by the variant and how much of it each cycle
+    consumable: (String, f64),
 }
 
 #[derive(Debug)]
@@ -107,6 +668,44 @@ pub enum LoadWorldError {
         recipe_name: String,
         resource_name: String,
     },
+    /// the name of the power resource did not appear in the list of resources
+    BadPowerResource {
+        resource_name: String,
+    },
+    /// the resource of a node group did not appear in the list of resources
+    BadNodeResource {
+        resource_name: String,
+    },
+    /// a recipe's `per_minute` is zero or negative, which would zero or flip its rates
+    BadPerMinute {
+        recipe_name: String,
+        per_minute: f64,
+    },
+    /// a recipe extends a recipe that doesn't exist
+    BadRecipeBase {
+        recipe_name: String,
+        base_name: String,
+    },
+    /// a recipe eventually extends itself
+    RecipeCycle {
+        recipe_name: String,
+    },
+    /// the game's docs file didn't have the expected structure
+    BadGameDocs {
+        reason: String,
+    },
+}
+
+/// loads a world file, or imports the game's docs file if `from_game_docs`
+pub fn load_world_from(
+    path: impl AsRef<std::path::Path>,
+    from_game_docs: bool,
+) -> Result<World, LoadWorldError> {
+    if from_game_docs {
+        import_game_docs(path)
+    } else {
+        load_world(path)
+    }
 }
 
 pub fn load_world(path: impl AsRef<std::path::Path>) -> Result<World, LoadWorldError> {
@@ -115,17 +714,52 @@ pub fn load_world(path: impl AsRef<std::path::Path>) -> Result<World, LoadWorldE
         Err(err) => return Err(LoadWorldError::IoError(err)),
     };
 
-    let world_json: WorldJson = match serde_json::from_reader(std::io::BufReader::new(file)) {
+    parse_world(std::io::BufReader::new(file))
+}
+
+fn parse_world(reader: impl std::io::Read) -> Result<World, LoadWorldError> {
+    let mut world_json: WorldJson = match serde_json::from_reader(reader) {
         Ok(world) => world,
         Err(err) => return Err(LoadWorldError::JsonError(err)),
     };
 
-    let mut world = World::default();
+    resolve_extends(&mut world_json.recipes)?;
+
+    let mut world = World {
+        belt_limit: world_json.belt_limit,
+        pipe_limit: world_json.pipe_limit,
+        description: world_json.description,
+        footprints: world_json.footprints,
+        node_rate: world_json.node_rate,
+        ..Default::default()
+    };
 
     // parse resources
-    for resource_name in world_json.resources {
-        world.resources.push(Resource {
-            name: resource_name,
+    for resource in world_json.resources {
+        world.resources.push(match resource {
+            ResourceJson::Name(name) => Resource {
+                name,
+                is_fluid: false,
+                aliases: Vec::new(),
+                category: None,
+                color: None,
+                points: None,
+            },
+            ResourceJson::Full {
+                name,
+                is_fluid,
+                aliases,
+                category,
+                color,
+                points,
+            } => Resource {
+                name,
+                is_fluid,
+                aliases,
+                category,
+                color,
+                points,
+            },
         });
     }
 
@@ -135,12 +769,32 @@ pub fn load_world(path: impl AsRef<std::path::Path>) -> Result<World, LoadWorldE
         tags,
         per_minute,
         rates,
+        preference,
+        note,
+        building,
+        aliases,
+        tier,
+        extends: _,
+        amplified,
     } in world_json.recipes
     {
+        if per_minute <= 0. {
+            return Err(LoadWorldError::BadPerMinute {
+                recipe_name: name,
+                per_minute,
+            });
+        }
+
         let mut recipe = Recipe {
             name: name.clone(),
             tags,
             rates: Vec::new(),
+            preference,
+            note,
+            building,
+            aliases,
+            tier,
+            amplifies: None,
         };
 
         // convert from resource names to recipe ids
@@ -157,8 +811,599 @@ pub fn load_world(path: impl AsRef<std::path::Path>) -> Result<World, LoadWorldE
             recipe.rates.push((resource_id, rate));
         }
 
+        let amplified = match amplified {
+            Some(AmplifiedJson {
+                output_multiplier,
+                consumable: (consumable_name, amount),
+            }) => {
+                let Some(consumable) = world.resource_id_of_name(&consumable_name) else {
+                    return Err(LoadWorldError::BadRecipeResource {
+                        recipe_name: name,
+                        resource_name: consumable_name,
+                    });
+                };
+
+                let mut rates: Vec<(ResourceId, f64)> = recipe
+                    .rates
+                    .iter()
+                    .map(|&(resource, rate)| {
+                        if rate > 0. {
+                            (resource, rate * output_multiplier)
+                        } else {
+                            (resource, rate)
+                        }
+                    })
+                    .collect();
+
+                let used = amount * per_minute;
+                match rates
+                    .iter_mut()
+                    .find(|(resource, _)| *resource == consumable)
+                {
+                    Some((_, rate)) => *rate -= used,
+                    None => rates.push((consumable, -used)),
+                }
+
+                Some(Recipe {
+                    name: format!("{} (Amplified)", name),
+                    rates,
+                    aliases: Vec::new(),
+                    amplifies: Some(RecipeId(world.recipes.len())),
+                    ..recipe.clone()
+                })
+            }
+            None => None,
+        };
+
         world.recipes.push(recipe);
+        world.recipes.extend(amplified);
+    }
+
+    // find the power resource
+    if let Some(resource_name) = world_json.power {
+        let Some(resource_id) = world.resource_id_of_name(&resource_name) else {
+            return Err(LoadWorldError::BadPowerResource { resource_name });
+        };
+
+        world.power = Some(resource_id);
+    }
+
+    for NodeGroupJson {
+        resource,
+        purity,
+        count,
+    } in world_json.nodes
+    {
+        let Some(resource_id) = world.resource_id_of_name(&resource) else {
+            return Err(LoadWorldError::BadNodeResource {
+                resource_name: resource,
+            });
+        };
+
+        world.nodes.push(NodeGroup {
+            resource: resource_id,
+            purity,
+            count,
+        });
+    }
+
+    for recipe in world.empty_recipes() {
+        warn!(
+            "recipe \"{}\" has no rates and will never be used",
+            world.name_of_recipe(recipe)
+        );
+    }
+
+    for (a, b) in world.detect_recipe_cycles_zero_cost() {
+        warn!(
+            "recipes \"{}\" and \"{}\" undo each other, consider disabling one",
+            world.name_of_recipe(a),
+            world.name_of_recipe(b)
+        );
+    }
+
+    Ok(world)
+}
+
+/// builds a world from the `Docs.json` the game ships with, which lists every item and recipe
+///
+/// only recipes made in a building are imported, scaled to per minute from the crafting duration.
+/// fluid amounts are stored in thousandths of a cubic meter so they are divided by 1000
+pub fn import_game_docs(path: impl AsRef<std::path::Path>) -> Result<World, LoadWorldError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => return Err(LoadWorldError::IoError(err)),
+    };
+
+    // the game writes the file as utf-16 with a byte order mark
+    let text = if bytes.starts_with(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+
+    let docs: serde_json::Value = match serde_json::from_str(text.trim_start_matches('\u{feff}')) {
+        Ok(docs) => docs,
+        Err(err) => return Err(LoadWorldError::JsonError(err)),
+    };
+
+    let bad_docs = |reason: &str| LoadWorldError::BadGameDocs {
+        reason: reason.into(),
+    };
+
+    let groups = docs
+        .as_array()
+        .ok_or_else(|| bad_docs("expected a list of classes"))?;
+
+    // display names and forms of every class, keyed by class name
+    let mut display_names = HashMap::new();
+    let mut fluid_classes = Vec::new();
+    let mut recipe_classes = Vec::new();
+
+    for group in groups {
+        let native_class = group["NativeClass"].as_str().unwrap_or_default();
+
+        let classes = group["Classes"]
+            .as_array()
+            .ok_or_else(|| bad_docs("a group has no classes"))?;
+
+        for class in classes {
+            let Some(class_name) = class["ClassName"].as_str() else {
+                continue;
+            };
+
+            if let Some(display_name) = class["mDisplayName"].as_str() {
+                display_names.insert(class_name.to_string(), display_name.to_string());
+            }
+
+            if matches!(class["mForm"].as_str(), Some("RF_LIQUID" | "RF_GAS")) {
+                fluid_classes.push(class_name.to_string());
+            }
+
+            if native_class.ends_with("FGRecipe'") {
+                recipe_classes.push(class);
+            }
+        }
+    }
+
+    let mut world = World {
+        from_game_docs: true,
+        ..Default::default()
+    };
+
+    for class in recipe_classes {
+        let name = class["mDisplayName"]
+            .as_str()
+            .ok_or_else(|| bad_docs("a recipe has no name"))?;
+
+        // the first building the recipe is made in, skipping the build gun and workbenches
+        let Some(building_class) = class["mProducedIn"]
+            .as_str()
+            .unwrap_or_default()
+            .split(',')
+            .map(docs_class_name)
+            .find(|class_name| class_name.starts_with("Build_"))
+        else {
+            continue;
+        };
+
+        let duration: f64 = class["mManufactoringDuration"]
+            .as_str()
+            .and_then(|duration| duration.parse().ok())
+            .filter(|duration: &f64| *duration > 0.)
+            .ok_or_else(|| bad_docs("a recipe has no duration"))?;
+
+        let per_minute = 60. / duration;
+
+        let building = display_names
+            .get(building_class)
+            .cloned()
+            .unwrap_or_else(|| {
+                building_class
+                    .trim_start_matches("Build_")
+                    .trim_end_matches("_C")
+                    .into()
+            });
+
+        let mut rates = Vec::new();
+
+        for (field, sign) in [("mIngredients", -1.), ("mProduct", 1.)] {
+            for (item_class, amount) in docs_item_amounts(class[field].as_str().unwrap_or_default())
+            {
+                let resource_name = display_names
+                    .get(item_class)
+                    .cloned()
+                    .unwrap_or_else(|| item_class.into());
+
+                let resource = match world.resource_id_of_name(&resource_name) {
+                    Some(resource) => resource,
+                    None => {
+                        world.resources.push(Resource {
+                            name: resource_name,
+                            is_fluid: fluid_classes.iter().any(|fluid| fluid == item_class),
+                            aliases: Vec::new(),
+                            category: None,
+                            color: None,
+                            points: None,
+                        });
+
+                        ResourceId(world.resources.len() - 1)
+                    }
+                };
+
+                let amount = if world.resources[resource.0].is_fluid {
+                    amount / 1000.
+                } else {
+                    amount
+                };
+
+                rates.push((resource, sign * amount * per_minute));
+            }
+        }
+
+        world.recipes.push(Recipe {
+            name: name.into(),
+            tags: vec![building.clone()],
+            rates,
+            preference: 0.,
+            note: None,
+            building: Some(building),
+            aliases: Vec::new(),
+            tier: None,
+            amplifies: None,
+        });
     }
 
     Ok(world)
 }
+
+/// the class name at the end of an unreal object path,
+/// like `Desc_OreIron_C` from `"/Game/.../Desc_OreIron.Desc_OreIron_C"'`
+fn docs_class_name(path: &str) -> &str {
+    let path = path.trim_matches(|c: char| "()\"' ".contains(c));
+
+    path.rsplit(['.', '/']).next().unwrap_or(path)
+}
+
+/// the item classes and amounts in a list like `((ItemClass=...,Amount=1),(ItemClass=...,Amount=2))`
+fn docs_item_amounts(list: &str) -> Vec<(&str, f64)> {
+    list.split("ItemClass=")
+        .skip(1)
+        .filter_map(|entry| {
+            let (path, rest) = entry.split_once(",Amount=")?;
+            let amount = rest.split([')', ',']).next()?.parse().ok()?;
+
+            Some((docs_class_name(path), amount))
+        })
+        .collect()
+}
+
+/// fills in the rates and tags of recipes that extend another recipe
+///
+/// each missing list is taken from the nearest recipe up the chain of bases that has one,
+/// the extending recipe keeps its own `per_minute`
+fn resolve_extends(recipes: &mut [RecipeJson]) -> Result<(), LoadWorldError> {
+    for index in 0..recipes.len() {
+        let mut chain = vec![index];
+
+        while let Some(base_name) = &recipes[*chain.last().unwrap()].extends {
+            let Some(base) = recipes.iter().position(|recipe| recipe.name == *base_name) else {
+                return Err(LoadWorldError::BadRecipeBase {
+                    recipe_name: recipes[*chain.last().unwrap()].name.clone(),
+                    base_name: base_name.clone(),
+                });
+            };
+
+            if chain.contains(&base) {
+                return Err(LoadWorldError::RecipeCycle {
+                    recipe_name: recipes[index].name.clone(),
+                });
+            }
+
+            chain.push(base);
+        }
+
+        let rates = chain
+            .iter()
+            .map(|&link| &recipes[link].rates)
+            .find(|rates| !rates.is_empty())
+            .cloned()
+            .unwrap_or_default();
+
+        let tags = chain
+            .iter()
+            .map(|&link| &recipes[link].tags)
+            .find(|tags| !tags.is_empty())
+            .cloned()
+            .unwrap_or_default();
+
+        recipes[index].rates = rates;
+        recipes[index].tags = tags;
+    }
+
+    Ok(())
+}
+
+/// fixtures shared with the tests of the other modules
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn resource(name: &str) -> Resource {
+        Resource {
+            name: name.into(),
+            is_fluid: false,
+            aliases: Vec::new(),
+            category: None,
+            color: None,
+            points: None,
+        }
+    }
+
+    pub fn recipe(name: &str, rates: Vec<(usize, f64)>) -> Recipe {
+        Recipe {
+            name: name.into(),
+            tags: Vec::new(),
+            rates: rates
+                .into_iter()
+                .map(|(index, rate)| (ResourceId(index), rate))
+                .collect(),
+            preference: 0.,
+            note: None,
+            building: None,
+            aliases: Vec::new(),
+            tier: None,
+            amplifies: None,
+        }
+    }
+
+    /// ore is smelted into ingots which are pressed into plates
+    pub fn plate_world() -> World {
+        World {
+            resources: vec![resource("Ore"), resource("Ingot"), resource("Plate")],
+            recipes: vec![
+                recipe("Smelt", vec![(0, -30.), (1, 30.)]),
+                recipe("Press", vec![(1, -30.), (2, 20.)]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn finds_unproducible_resources() {
+        let world = World {
+            resources: vec![resource("Ore"), resource("Ingot"), resource("Plate")],
+            recipes: vec![
+                recipe("Mine", vec![(0, 60.)]),
+                recipe("Press", vec![(1, -30.), (2, 20.)]),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(world.unproducible_resources(), vec![ResourceId(1)]);
+    }
+
+    #[test]
+    fn recipes_extend_a_base() {
+        let world = parse_world(
+            r#"{
+                "resources": ["Ore", "Ingot"],
+                "recipes": [
+                    { "name": "Smelt", "tags": ["Smelter"], "per_minute": 30, "rates": [["Ore", -1], ["Ingot", 1]] },
+                    { "name": "Smelt 150%", "per_minute": 45, "extends": "Smelt" },
+                    { "name": "Smelt 250%", "per_minute": 75, "extends": "Smelt 150%" }
+                ]
+            }"#
+            .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            world.recipes[1].rates,
+            vec![(ResourceId(0), -45.), (ResourceId(1), 45.)]
+        );
+        assert_eq!(
+            world.recipes[2].rates,
+            vec![(ResourceId(0), -75.), (ResourceId(1), 75.)]
+        );
+        assert_eq!(world.recipes[2].tags, vec!["Smelter".to_string()]);
+    }
+
+    #[test]
+    fn compares_recipes_per_unit_of_output() {
+        let world = World {
+            resources: vec![
+                resource("Ore"),
+                resource("Ingot"),
+                resource("Slag"),
+                resource("Power"),
+            ],
+            recipes: vec![
+                recipe("Smelt", vec![(0, -30.), (1, 30.), (3, -4.)]),
+                recipe("Refine", vec![(0, -20.), (1, 40.), (2, 10.), (3, -20.)]),
+                recipe("Mine", vec![(0, 60.)]),
+            ],
+            power: Some(ResourceId(3)),
+            ..Default::default()
+        };
+
+        let comparisons = world.recipe_comparison(ResourceId(1));
+        assert_eq!(comparisons.len(), 2);
+
+        let refine = &comparisons[1];
+        assert_eq!(refine.recipe, RecipeId(1));
+        assert_eq!(refine.inputs, vec![(ResourceId(0), 0.5)]);
+        assert_eq!(refine.byproducts, vec![(ResourceId(2), 0.25)]);
+        assert_eq!(refine.power, Some(0.5));
+        assert_eq!(comparisons[0].total_inputs(), 1.);
+    }
+
+    #[test]
+    fn amplified_variants_load_as_linked_recipes() {
+        let world = parse_world(
+            r#"{
+                "resources": ["Ore", "Ingot", "Shard"],
+                "recipes": [
+                    {
+                        "name": "Smelt",
+                        "per_minute": 30,
+                        "rates": [["Ore", -1], ["Ingot", 1]],
+                        "amplified": { "output_multiplier": 2, "consumable": ["Shard", 0.1] }
+                    },
+                    { "name": "Scrap", "per_minute": 10, "rates": [["Ingot", 1]] }
+                ]
+            }"#
+            .as_bytes(),
+        )
+        .unwrap();
+
+        let amplified = world.recipe_id_of_name("Smelt (Amplified)").unwrap();
+        assert_eq!(amplified, RecipeId(1));
+        assert_eq!(world.recipes[1].amplifies, Some(RecipeId(0)));
+        assert_eq!(
+            world.recipes[1].rates,
+            vec![
+                (ResourceId(0), -30.),
+                (ResourceId(1), 60.),
+                (ResourceId(2), -3.)
+            ]
+        );
+        assert_eq!(world.recipe_id_of_name("Scrap"), Some(RecipeId(2)));
+
+        let problem = crate::builder::Problem {
+            disabled: vec![RecipeId(0)],
+            ..Default::default()
+        };
+        assert!(problem.is_disabled(&world, amplified));
+    }
+
+    #[test]
+    fn filtering_keeps_amplified_variants_linked() {
+        let mut world = World {
+            resources: vec![resource("Ore"), resource("Ingot"), resource("Shard")],
+            recipes: vec![
+                recipe("Scrap", vec![(1, 10.)]),
+                recipe("Smelt", vec![(0, -30.), (1, 30.)]),
+                recipe("Smelt (Amplified)", vec![(0, -30.), (1, 60.), (2, -3.)]),
+            ],
+            ..Default::default()
+        };
+        world.recipes[2].amplifies = Some(RecipeId(1));
+
+        let filtered = world
+            .clone()
+            .filter_recipes(|RecipeId(index), _| index != 0);
+        assert_eq!(filtered.recipes[1].amplifies, Some(RecipeId(0)));
+
+        let without_base = world.filter_recipes(|RecipeId(index), _| index != 1);
+        assert_eq!(without_base.recipes[1].amplifies, None);
+    }
+
+    #[test]
+    fn imports_recipes_from_game_docs() {
+        let world = import_game_docs("docs_sample.json").unwrap();
+        assert!(world.from_game_docs);
+
+        let ingot = world.recipe_id_of_name("Iron Ingot").unwrap();
+        let ore = world.resource_id_of_name("Iron Ore").unwrap();
+        let ingot_resource = world.resource_id_of_name("Iron Ingot").unwrap();
+
+        let recipe = &world.recipes[ingot.0];
+        assert_eq!(recipe.rates, vec![(ore, -30.), (ingot_resource, 30.)]);
+        assert_eq!(recipe.building.as_deref(), Some("Smelter"));
+
+        // workbench only recipes are skipped
+        assert!(world.recipe_id_of_name("Iron Plate (Workbench)").is_none());
+    }
+
+    #[test]
+    fn tiers_gate_recipes() {
+        let world = parse_world(
+            r#"{
+                "resources": ["Ore", "Ingot"],
+                "recipes": [
+                    { "name": "Smelt", "tier": 0, "per_minute": 30, "rates": [["Ore", -1], ["Ingot", 1]] },
+                    { "name": "Alt Smelt", "tier": 3, "per_minute": 45, "rates": [["Ore", -1], ["Ingot", 1]] },
+                    { "name": "Scrap", "per_minute": 10, "rates": [["Ingot", 1]] }
+                ]
+            }"#
+            .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(world.max_tier(), Some(3));
+
+        let available = |max_tier| {
+            world
+                .recipes
+                .iter()
+                .filter(|recipe| recipe.within_tier(max_tier))
+                .map(|recipe| recipe.name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(available(2), vec!["Smelt", "Scrap"]);
+        assert_eq!(available(3), vec!["Smelt", "Alt Smelt", "Scrap"]);
+    }
+
+    #[test]
+    fn extends_cycles_are_rejected() {
+        let result = parse_world(
+            r#"{
+                "resources": ["Ore"],
+                "recipes": [
+                    { "name": "A", "per_minute": 30, "extends": "B" },
+                    { "name": "B", "per_minute": 30, "extends": "A" }
+                ]
+            }"#
+            .as_bytes(),
+        );
+
+        assert!(matches!(result, Err(LoadWorldError::RecipeCycle { .. })));
+    }
+
+    #[test]
+    fn zero_per_minute_is_rejected() {
+        let result = parse_world(
+            r#"{
+                "resources": ["Ore", "Ingot"],
+                "recipes": [
+                    { "name": "Smelt", "per_minute": 0, "rates": [["Ore", -1], ["Ingot", 1]] }
+                ]
+            }"#
+            .as_bytes(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(LoadWorldError::BadPerMinute { recipe_name, .. }) if recipe_name == "Smelt"
+        ));
+    }
+
+    #[test]
+    fn detects_inverse_recipes() {
+        let world = World {
+            resources: vec![
+                resource("Water"),
+                resource("Canister"),
+                resource("Packaged Water"),
+            ],
+            recipes: vec![
+                recipe("Package", vec![(0, -60.), (1, -60.), (2, 60.)]),
+                recipe("Unpackage", vec![(2, -120.), (0, 120.), (1, 120.)]),
+                recipe("Spill", vec![(2, -60.), (1, 60.)]),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            world.detect_recipe_cycles_zero_cost(),
+            vec![(RecipeId(0), RecipeId(1))]
+        );
+    }
+}